@@ -0,0 +1,72 @@
+//! Versions of the drand HTTP API, and the paths of each endpoint.
+//! The v1 API serves a chain at the root of a node (`/info`, `/public/latest`) or under its chain
+//! hash (`/{chain hash}/info`). The v2 API keys beacons by beacon id
+//! (`/v2/beacons/{beacon id}/rounds/latest`) or chain hash (`/v2/chains/{chain hash}/info`).
+
+use codec::{Decode, Encode, MaxEncodedLen};
+use scale_info::prelude::{format, string::String};
+use serde::{Deserialize, Serialize};
+use sp_runtime::RuntimeDebug;
+
+use crate::{util::vec_u8_to_hex, Client, ClientError, Round, RoundRaw, RoundV2Raw};
+
+/// Version of the HTTP API used by a `Client`.
+#[derive(
+    Encode,
+    Decode,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    RuntimeDebug,
+    scale_info::TypeInfo,
+    MaxEncodedLen,
+    Serialize,
+    Deserialize,
+)]
+pub enum ApiVersion {
+    #[default]
+    V1,
+    V2,
+}
+
+/// An endpoint of the API.
+pub(crate) enum ApiPath<'a> {
+    Chains,
+    Info,
+    Latest,
+    Round(u64),
+    /// Info of the chain with the given hash.
+    ChainInfo(&'a [u8]),
+}
+
+impl ApiVersion {
+    /// Path of `path`, relative to the endpoint. The v2 API addresses the beacon `beacon_id`.
+    pub(crate) fn path(&self, beacon_id: &str, path: ApiPath) -> String {
+        match (self, path) {
+            (ApiVersion::V1, ApiPath::Chains) => "/chains".into(),
+            (ApiVersion::V1, ApiPath::Info) => "/info".into(),
+            (ApiVersion::V1, ApiPath::Latest) => "/public/latest".into(),
+            (ApiVersion::V1, ApiPath::Round(round)) => format!("/public/{round}"),
+            (ApiVersion::V1, ApiPath::ChainInfo(hash)) => format!("/{}/info", vec_u8_to_hex(hash)),
+            (ApiVersion::V2, ApiPath::Chains) => "/v2/chains".into(),
+            (ApiVersion::V2, ApiPath::Info) => format!("/v2/beacons/{beacon_id}/info"),
+            (ApiVersion::V2, ApiPath::Latest) => format!("/v2/beacons/{beacon_id}/rounds/latest"),
+            (ApiVersion::V2, ApiPath::Round(round)) => {
+                format!("/v2/beacons/{beacon_id}/rounds/{round}")
+            }
+            (ApiVersion::V2, ApiPath::ChainInfo(hash)) => {
+                format!("/v2/chains/{}/info", vec_u8_to_hex(hash))
+            }
+        }
+    }
+
+    /// Deserializes a round, in the shape of this API version.
+    pub(crate) fn parse_round(&self, body: &[u8]) -> Result<Round, ClientError> {
+        Ok(match self {
+            ApiVersion::V1 => Round::from(Client::parse_json::<RoundRaw>(body)?),
+            ApiVersion::V2 => Round::from(Client::parse_json::<RoundV2Raw>(body)?),
+        })
+    }
+}
@@ -0,0 +1,265 @@
+//! Async variant of the `Client` for `std` consumers (e.g. tokio services), enabled with the
+//! `async` feature. Besides the usual endpoints, `AsyncClient::watch` yields every new verified
+//! round right after its expected emission time.
+//!
+//! Only the endpoint, `chain_info`, `api_version`, `verification` and the headers with a plain
+//! value of the `Client` are used. The settings backed by the offchain local storage (`cache`,
+//! `lock`, `stats`, `retry` and secret headers) need an offchain worker and are ignored, as are
+//! the relays and the transport settings, which are up to the `AsyncTransport`. Redirects are
+//! followed as by the `Client`: at most `MAX_REDIRECTS` times, to the same scheme, with the
+//! headers only sent to the origin of the endpoint.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use async_trait::async_trait;
+use futures::{stream, Stream};
+
+use crate::{
+    api::ApiPath,
+    http::{headers_for, redirect_of},
+    Chains, ChainsRaw, Client, ClientError, HttpResponse, Info, Round, Verification, MAX_REDIRECTS,
+};
+
+/// Async counterpart of `Transport`.
+#[async_trait]
+pub trait AsyncTransport: Send + Sync {
+    /// HTTP GET on `url`. Returns the response even if the status code is not `200`,
+    /// errors are only for requests that could not be completed.
+    async fn get(&self, url: &str) -> Result<HttpResponse, ClientError>;
+
+    /// HTTP GET on `url`, sending `headers` along. Transports that can't send headers ignore
+    /// them.
+    async fn get_with_headers(
+        &self,
+        url: &str,
+        headers: &[(String, String)],
+    ) -> Result<HttpResponse, ClientError> {
+        if !headers.is_empty() {
+            log::warn!("Headers not sent to {}", url);
+        }
+        self.get(url).await
+    }
+}
+
+/// Runs the blocking native transport on tokio's blocking thread pool.
+#[cfg(feature = "native-http")]
+#[async_trait]
+impl AsyncTransport for crate::NativeTransport {
+    async fn get(&self, url: &str) -> Result<HttpResponse, ClientError> {
+        let transport = self.clone();
+        let url = url.to_owned();
+        tokio::task::spawn_blocking(move || crate::Transport::get(&transport, &url))
+            .await
+            .map_err(|_| ClientError::Http)?
+    }
+
+    async fn get_with_headers(
+        &self,
+        url: &str,
+        headers: &[(String, String)],
+    ) -> Result<HttpResponse, ClientError> {
+        let transport = self.clone();
+        let url = url.to_owned();
+        let headers = headers.to_vec();
+        tokio::task::spawn_blocking(move || {
+            crate::Transport::get_with_headers(&transport, &url, &headers)
+        })
+        .await
+        .map_err(|_| ClientError::Http)?
+    }
+}
+
+/// Retry policy of `AsyncClient::watch` when a round is late.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WatchConfig {
+    /// Delay before the first retry, doubled after every failed attempt.
+    pub initial_backoff: Duration,
+    /// Upper bound of the delay between two attempts.
+    pub max_backoff: Duration,
+    /// Attempts for a single round before reporting the error and moving on to the next round.
+    pub max_attempts: u32,
+}
+
+impl Default for WatchConfig {
+    fn default() -> Self {
+        WatchConfig {
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(8),
+            max_attempts: 10,
+        }
+    }
+}
+
+/// Async drand client, holding the `Client` configuration and the transport used to fetch.
+/// See the module documentation for the settings of the `Client` it ignores.
+pub struct AsyncClient<T> {
+    client: Client,
+    transport: T,
+    watch_config: WatchConfig,
+}
+
+impl<T: AsyncTransport> AsyncClient<T> {
+    pub fn new(client: Client, transport: T) -> Self {
+        if client.cache.is_some()
+            || client.lock.is_some()
+            || client.stats.is_some()
+            || client.retry.is_some()
+        {
+            log::warn!("The cache, lock, stats and retry settings are ignored by AsyncClient");
+        }
+        AsyncClient {
+            client,
+            transport,
+            watch_config: WatchConfig::default(),
+        }
+    }
+
+    /// Overrides the retry policy of `watch`.
+    pub fn with_watch_config(mut self, watch_config: WatchConfig) -> Self {
+        self.watch_config = watch_config;
+        self
+    }
+
+    /// The underlying client configuration.
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// The transport used to fetch.
+    pub fn transport(&self) -> &T {
+        &self.transport
+    }
+
+    /// HTTP GET on the chain's `/chains` endpoint.
+    pub async fn chains(&self) -> Result<Chains, ClientError> {
+        let body = self.get_body(&self.client.path(ApiPath::Chains)).await?;
+        let chains_raw: ChainsRaw = Client::parse_json(&body)?;
+
+        Ok(Chains::from(chains_raw))
+    }
+
+    /// HTTP GET on the chain's `/info` endpoint.
+    pub async fn info(&self) -> Result<Info, ClientError> {
+        let body = self.get_body(&self.client.path(ApiPath::Info)).await?;
+
+        self.client.parse_info(&body)
+    }
+
+    /// Queries and verifies a round of randomness.
+    pub async fn round(&self, round: u64) -> Result<Round, ClientError> {
+        let chain_info = self.client.chain_info()?;
+        let body = self
+            .get_body(&self.client.path(ApiPath::Round(round)))
+            .await?;
+        let round = self.client.parse_round(&body)?;
+
+        Client::verify_round(&round, &chain_info)?;
+
+        Ok(round)
+    }
+
+    /// This fetches the latest round from the drand server.
+    /// With `Verification::Strict`, the round is verified.
+    pub async fn latest(&self) -> Result<Round, ClientError> {
+        let body = self.get_body(&self.client.path(ApiPath::Latest)).await?;
+        let round = self.client.parse_round(&body)?;
+
+        if self.client.verification == Verification::Strict {
+            Client::verify_round(&round, &self.client.chain_info()?)?;
+        }
+
+        Ok(round)
+    }
+
+    /// Stream of verified rounds, starting with the round being emitted now.
+    /// Requires `chain_info` to be configured, else the stream ends after yielding
+    /// `ChainNotConfigured`.
+    pub fn watch(&self) -> impl Stream<Item = Result<Round, ClientError>> + Unpin + '_ {
+        let next = self
+            .client
+            .chain_info
+            .as_ref()
+            .map(|info| info.round_at(unix_now().as_secs()).max(1))
+            .unwrap_or(1);
+        self.watch_from(next)
+    }
+
+    /// Stream of verified rounds, starting at round `from`.
+    /// Each round is requested once its expected emission time (computed from the chain's
+    /// `genesis_time` and `period`) has passed, so past rounds are yielded right away.
+    /// A round that is not available yet is retried with exponential backoff, and after
+    /// `WatchConfig::max_attempts` the error is yielded and the stream moves on to the next round.
+    /// Without `chain_info`, the stream ends after yielding `ChainNotConfigured`.
+    pub fn watch_from(
+        &self,
+        from: u64,
+    ) -> impl Stream<Item = Result<Round, ClientError>> + Unpin + '_ {
+        Box::pin(stream::unfold(Some(from), move |next| async move {
+            let next = next?;
+            match self.watch_round(next).await {
+                // no round can be verified without a chain, so there is nothing left to watch
+                Err(ClientError::ChainNotConfigured) => {
+                    Some((Err(ClientError::ChainNotConfigured), None))
+                }
+                result => Some((result, Some(next + 1))),
+            }
+        }))
+    }
+
+    async fn watch_round(&self, round: u64) -> Result<Round, ClientError> {
+        let chain_info = self.client.chain_info()?;
+
+        let emission = Duration::from_secs(chain_info.round_time(round));
+        if let Some(wait) = emission.checked_sub(unix_now()) {
+            tokio::time::sleep(wait).await;
+        }
+
+        let mut backoff = self.watch_config.initial_backoff;
+        let mut attempt = 1;
+        loop {
+            match self.round(round).await {
+                Ok(round) => return Ok(round),
+                Err(e) if attempt >= self.watch_config.max_attempts => {
+                    log::warn!(
+                        "Round {} still unavailable after {} attempts",
+                        round,
+                        attempt
+                    );
+                    return Err(e);
+                }
+                Err(_) => {
+                    tokio::time::sleep(backoff).await;
+                    backoff = (backoff * 2).min(self.watch_config.max_backoff);
+                    attempt += 1;
+                }
+            }
+        }
+    }
+
+    /// Body of the `200` response to `path`, relative to the endpoint, following redirects.
+    /// Headers with a secret value aren't sent, as there is no offchain local storage to read it
+    /// from.
+    async fn get_body(&self, path: &str) -> Result<Vec<u8>, ClientError> {
+        let url = String::from_utf8(self.client.url(path)).map_err(|_| ClientError::Http)?;
+        let headers = self.client.resolve_headers(|_| None);
+
+        let mut target = url.clone();
+        for _ in 0..=MAX_REDIRECTS {
+            let sent = headers_for(&url, &target, &headers, &[]);
+            let response = self.transport.get_with_headers(&target, &sent).await?;
+            match redirect_of(&target, &response)? {
+                Some(next) => target = next,
+                None => return Client::check_response(response),
+            }
+        }
+
+        log::warn!("Too many redirects from {}", url);
+        Err(ClientError::Http)
+    }
+}
+
+fn unix_now() -> Duration {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+}
@@ -0,0 +1,95 @@
+//! Detection and backfilling of the rounds missing from the on-chain record, e.g. after a node
+//! restart or a relay outage.
+//!
+//! The pallet keeps a `BackfillProgress` in its storage: every round up to `last_round` is
+//! recorded on-chain. Each offchain worker fetches the next missing rounds (at most `max_rounds`
+//! per block) with `Client::backfill`, and submits them; the pallet records them and advances the
+//! progress with `BackfillProgress::advance`. Rounds are fetched through `Client::round`, so with
+//! the cache enabled the rounds of a batch that isn't included yet aren't fetched again.
+
+use codec::{Decode, Encode, MaxEncodedLen};
+use sp_runtime::RuntimeDebug;
+use sp_std::{ops::RangeInclusive, vec::Vec};
+
+use crate::{Client, ClientError, Info, Round, Transport};
+
+/// The rounds recorded on-chain without gaps.
+#[derive(
+    Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, scale_info::TypeInfo, MaxEncodedLen,
+)]
+pub struct BackfillProgress {
+    /// Every round from the first tracked one up to this one is recorded.
+    pub last_round: u64,
+}
+
+impl BackfillProgress {
+    /// Progress of a record starting at `first_round`, with nothing recorded yet.
+    pub fn starting_at(first_round: u64) -> Self {
+        BackfillProgress {
+            last_round: first_round.saturating_sub(1),
+        }
+    }
+
+    /// Whether `round` is recorded, as far as the progress tells.
+    pub fn is_available(&self, round: u64) -> bool {
+        round <= self.last_round
+    }
+
+    /// Rounds emitted by the chain `info` at `now` (unix seconds) and not recorded yet.
+    pub fn missing(&self, info: &Info, now: u64) -> RangeInclusive<u64> {
+        self.last_round.saturating_add(1)..=info.round_at(now)
+    }
+
+    /// Advances over the recorded `rounds`, which may be in any order. Rounds after a gap don't
+    /// advance the progress. Returns by how many rounds it advanced.
+    pub fn advance(&mut self, rounds: &[Round]) -> u64 {
+        let mut numbers: Vec<u64> = rounds.iter().map(|round| round.round).collect();
+        numbers.sort_unstable();
+
+        let start = self.last_round;
+        for number in numbers {
+            if number == self.last_round + 1 {
+                self.last_round = number;
+            }
+        }
+        self.last_round - start
+    }
+}
+
+impl Client {
+    /// Fetches and verifies the next rounds missing from `progress`, at most `max_rounds`, in
+    /// order. Stops at the first round that can't be fetched, so the batch has no gaps.
+    /// Uses the offchain worker timestamp to know which rounds are emitted.
+    pub fn backfill(
+        &self,
+        progress: &BackfillProgress,
+        max_rounds: u32,
+    ) -> Result<Vec<Round>, ClientError> {
+        let now = sp_io::offchain::timestamp().unix_millis() / 1_000;
+        self.backfill_with(&self.transport, progress, now, max_rounds)
+    }
+
+    /// Same as `backfill` at `now` (unix seconds), fetching through the given transport.
+    /// Fails only if not even the first missing round could be fetched.
+    pub fn backfill_with<T: Transport>(
+        &self,
+        transport: &T,
+        progress: &BackfillProgress,
+        now: u64,
+        max_rounds: u32,
+    ) -> Result<Vec<Round>, ClientError> {
+        let info = self.chain_info()?;
+        let mut rounds = Vec::new();
+        for number in progress.missing(&info, now).take(max_rounds as usize) {
+            match self.round_with(transport, number) {
+                Ok(round) => rounds.push(round),
+                Err(e) if rounds.is_empty() => return Err(e),
+                Err(e) => {
+                    log::warn!("Backfill stopped at round {}: {:?}", number, e);
+                    break;
+                }
+            }
+        }
+        Ok(rounds)
+    }
+}
@@ -0,0 +1,109 @@
+//! BLS12-381 helpers shared by the signature verification of every drand scheme and by timelock
+//! encryption. Points use the compressed (zcash) encoding served by drand.
+
+use ark_bls12_381::{g1, g2, G1Affine, G1Projective, G2Affine, G2Projective};
+use ark_ec::hashing::{
+    curve_maps::wb::WBMap, map_to_curve_hasher::MapToCurveBasedHasher, HashToCurve,
+};
+use ark_ff::field_hashers::DefaultFieldHasher;
+use ark_serialize::CanonicalDeserialize;
+use sha2::{Digest, Sha256};
+
+use crate::Scheme;
+
+/// Domain separation tag of hashing to G1, as specified by RFC 9380.
+pub const G1_DST: &[u8] = b"BLS_SIG_BLS12381G1_XMD:SHA-256_SSWU_RO_NUL_";
+/// Domain separation tag of hashing to G2.
+pub const G2_DST: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_NUL_";
+
+/// The message signed by the chain for `round`: the hash of the round number, preceded by the
+/// previous signature for chained schemes.
+pub fn round_message(scheme: Scheme, round: u64, previous_signature: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    if scheme.is_chained() {
+        hasher.update(previous_signature);
+    }
+    hasher.update(round.to_be_bytes());
+    hasher.finalize().into()
+}
+
+/// Hashes `msg` onto G1 (for schemes with the public key on G2) or G2 (public key on G1), as the
+/// chain does before signing it. `None` for the schemes on another curve than BLS12-381.
+pub(crate) fn hash_to_signature_group(scheme: Scheme, msg: &[u8]) -> Option<SignaturePoint> {
+    match scheme {
+        Scheme::PedersenBlsChained | Scheme::PedersenBlsUnchained => {
+            hash_to_g2(G2_DST, msg).map(SignaturePoint::G2)
+        }
+        // this scheme predates RFC 9380 and uses the G2 tag to hash onto G1
+        Scheme::BlsUnchainedOnG1 => hash_to_g1(G2_DST, msg).map(SignaturePoint::G1),
+        Scheme::BlsUnchainedG1Rfc9380 => hash_to_g1(G1_DST, msg).map(SignaturePoint::G1),
+        Scheme::BlsBn254UnchainedOnG1 => None,
+    }
+}
+
+/// A point of the signature group of a scheme.
+pub(crate) enum SignaturePoint {
+    G1(G1Affine),
+    G2(G2Affine),
+}
+
+pub(crate) fn hash_to_g1(dst: &[u8], msg: &[u8]) -> Option<G1Affine> {
+    MapToCurveBasedHasher::<G1Projective, DefaultFieldHasher<Sha256, 128>, WBMap<g1::Config>>::new(
+        dst,
+    )
+    .ok()?
+    .hash(msg)
+    .ok()
+}
+
+pub(crate) fn hash_to_g2(dst: &[u8], msg: &[u8]) -> Option<G2Affine> {
+    MapToCurveBasedHasher::<G2Projective, DefaultFieldHasher<Sha256, 128>, WBMap<g2::Config>>::new(
+        dst,
+    )
+    .ok()?
+    .hash(msg)
+    .ok()
+}
+
+/// Decodes a compressed G1 point, checking it is in the prime order subgroup.
+pub(crate) fn g1_from_bytes(bytes: &[u8]) -> Option<G1Affine> {
+    G1Affine::deserialize_compressed(bytes).ok()
+}
+
+/// Decodes a compressed G2 point, checking it is in the prime order subgroup.
+pub(crate) fn g2_from_bytes(bytes: &[u8]) -> Option<G2Affine> {
+    G2Affine::deserialize_compressed(bytes).ok()
+}
+
+/// Verifies `signature` on `msg` against `public_key` for any scheme.
+/// Returns `None` if a point can't be decoded.
+pub fn verify_signature(
+    scheme: Scheme,
+    public_key: &[u8],
+    msg: &[u8],
+    signature: &[u8],
+) -> Option<bool> {
+    use ark_bls12_381::Bls12_381;
+    use ark_ec::{pairing::Pairing, AffineRepr};
+
+    match hash_to_signature_group(scheme, msg)? {
+        // e(pk, H(m)) == e(g1, sig)
+        SignaturePoint::G2(hashed) => {
+            let public_key = g1_from_bytes(public_key)?;
+            let signature = g2_from_bytes(signature)?;
+            Some(
+                Bls12_381::pairing(public_key, hashed)
+                    == Bls12_381::pairing(G1Affine::generator(), signature),
+            )
+        }
+        // e(H(m), pk) == e(sig, g2)
+        SignaturePoint::G1(hashed) => {
+            let public_key = g2_from_bytes(public_key)?;
+            let signature = g1_from_bytes(signature)?;
+            Some(
+                Bls12_381::pairing(hashed, public_key)
+                    == Bls12_381::pairing(signature, G2Affine::generator()),
+            )
+        }
+    }
+}
@@ -0,0 +1,310 @@
+//! Builder of a `Client` configuration, checking that it is consistent before it is stored
+//! on-chain or used, rather than failing on the first request:
+//! - the endpoint and relays are valid `Endpoint`s;
+//! - the chain info hashes to its chain hash, and to the pinned chain hash and scheme if any;
+//! - the cache and `Verification::Strict` have a chain to verify rounds against, and the lock
+//!   has a cache to fill, and is held for as long as a fetch takes at most (every attempt, up
+//!   to the deadlines of the transport, and the waits between them);
+//! - with the v1 API, the endpoint and relays of a chain other than a `default` one are under
+//!   its chain hash, where relays serve it (the v2 API addresses the chain in its paths);
+//! - headers are for a configured endpoint or relay, given by its URL or, for a chain under its
+//!   chain hash, by the URL of the relay.
+//!
+//! `ClientBuilder::network` (and `ClientBuilder::mainnet` or `ClientBuilder::quicknet`) start
+//! from a network of the registry, served by its relays.
+
+extern crate alloc;
+
+use alloc::string::{String, ToString};
+use sp_runtime::RuntimeDebug;
+use sp_std::vec::Vec;
+
+use crate::{
+    util::vec_u8_to_hex, ApiVersion, CacheConfig, Client, ClientError, Endpoint, HeaderValue, Info,
+    LockConfig, Network, OffchainTransport, RetryConfig, Scheme, StatsConfig, Transport,
+    Verification, DEFAULT_BEACON_ID, MAINNET, MAX_RELAYS, QUICKNET,
+};
+
+/// Builder of a `Client`. Nothing is checked until `build`.
+#[derive(Clone, Default, RuntimeDebug)]
+pub struct ClientBuilder {
+    endpoint: Option<String>,
+    relays: Vec<String>,
+    chain_info: Option<Info>,
+    chain_hash: Option<Vec<u8>>,
+    scheme: Option<Scheme>,
+    api_version: ApiVersion,
+    transport: OffchainTransport,
+    cache: Option<CacheConfig>,
+    lock: Option<LockConfig>,
+    stats: Option<StatsConfig>,
+    retry: Option<RetryConfig>,
+    headers: Vec<(String, String, HeaderValue)>,
+    user_agent: Option<String>,
+    verification: Verification,
+}
+
+impl ClientBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The chain of `network`, from its relays.
+    pub fn network(network: &Network) -> Self {
+        let mut endpoints = network.endpoints();
+        let endpoint = endpoints.remove(0);
+        let mut builder = Self::new()
+            .with_endpoint(&endpoint)
+            .with_chain_info(network.info());
+        builder.relays = endpoints;
+        builder
+    }
+
+    /// The League of Entropy mainnet `default` chain, from `MAINNET_ENDPOINT` and
+    /// `MAINNET_RELAYS`.
+    pub fn mainnet() -> Self {
+        Self::network(&MAINNET)
+    }
+
+    /// The League of Entropy mainnet `quicknet` chain, served under its chain hash by
+    /// `MAINNET_ENDPOINT` and `MAINNET_RELAYS`.
+    pub fn quicknet() -> Self {
+        Self::network(&QUICKNET)
+    }
+
+    /// Sets the endpoint requested first.
+    pub fn with_endpoint(mut self, url: &str) -> Self {
+        self.endpoint = Some(url.to_string());
+        self
+    }
+
+    /// Sets the relays tried after the endpoint, replacing any previous ones.
+    pub fn with_relays(mut self, urls: &[&str]) -> Self {
+        self.relays = urls.iter().map(|url| url.to_string()).collect();
+        self
+    }
+
+    /// Sets the chain of the client.
+    pub fn with_chain_info(mut self, info: Info) -> Self {
+        self.chain_info = Some(info);
+        self
+    }
+
+    /// Pins the chain hash. Without `chain_info`, `build` takes the chain info of `hash` from
+    /// the registry of networks, or else fetches it from the endpoint.
+    pub fn with_chain_hash(mut self, hash: &[u8]) -> Self {
+        self.chain_hash = Some(hash.to_vec());
+        self
+    }
+
+    /// Requires the chain to use `scheme`.
+    pub fn with_scheme(mut self, scheme: Scheme) -> Self {
+        self.scheme = Some(scheme);
+        self
+    }
+
+    pub fn with_api_version(mut self, api_version: ApiVersion) -> Self {
+        self.api_version = api_version;
+        self
+    }
+
+    /// Sets the time allowed to send a request and receive the response head, in milliseconds.
+    pub fn with_timeout(mut self, timeout_ms: u64) -> Self {
+        self.transport.timeout_ms = timeout_ms;
+        self
+    }
+
+    /// Sets the time allowed to read a response body, in milliseconds.
+    pub fn with_read_timeout(mut self, read_timeout_ms: u64) -> Self {
+        self.transport.read_timeout_ms = read_timeout_ms;
+        self
+    }
+
+    /// Sets the maximum size of a response body.
+    pub fn with_max_body_size(mut self, max_body_size: u32) -> Self {
+        self.transport.max_body_size = max_body_size;
+        self
+    }
+
+    pub fn with_cache(mut self, cache: CacheConfig) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Locks fetches across concurrent offchain workers. Requires a cache.
+    pub fn with_lock(mut self, lock: LockConfig) -> Self {
+        self.lock = Some(lock);
+        self
+    }
+
+    pub fn with_stats(mut self, stats: StatsConfig) -> Self {
+        self.stats = Some(stats);
+        self
+    }
+
+    pub fn with_retry(mut self, retry: RetryConfig) -> Self {
+        self.retry = Some(retry);
+        self
+    }
+
+    /// Adds a header to the requests to `endpoint`, or to every endpoint if it's empty.
+    pub fn with_header(mut self, endpoint: &str, name: &str, value: HeaderValue) -> Self {
+        self.headers
+            .push((endpoint.to_string(), name.to_string(), value));
+        self
+    }
+
+    pub fn with_user_agent(mut self, user_agent: &str) -> Self {
+        self.user_agent = Some(user_agent.to_string());
+        self
+    }
+
+    pub fn with_verification(mut self, verification: Verification) -> Self {
+        self.verification = verification;
+        self
+    }
+
+    /// Checks the configuration and builds the client. Only makes a request (through the
+    /// offchain worker HTTP API) to fetch the chain info of a pinned chain hash.
+    pub fn build(self) -> Result<Client, ClientError> {
+        let transport = self.transport;
+        self.build_with(&transport)
+    }
+
+    /// Same as `build`, fetching through the given transport.
+    pub fn build_with<T: Transport>(self, transport: &T) -> Result<Client, ClientError> {
+        let endpoint = self.endpoint.as_deref().ok_or_else(|| {
+            log::warn!("No endpoint configured");
+            ClientError::InvalidConfiguration
+        })?;
+        if self.relays.len() > MAX_RELAYS as usize {
+            log::warn!("More than {} relays", MAX_RELAYS);
+            return Err(ClientError::InvalidConfiguration);
+        }
+        if self.lock.is_some() && self.cache.is_none() {
+            log::warn!("The lock requires a cache");
+            return Err(ClientError::InvalidConfiguration);
+        }
+        if let Some(lock) = &self.lock {
+            let (attempts, waits) = self.retry.as_ref().map_or((1, 0), |retry| {
+                (retry.max_attempts.max(1), retry.max_wait_ms())
+            });
+            let fetch_ms = self
+                .transport
+                .timeout_ms
+                .saturating_add(self.transport.read_timeout_ms)
+                .saturating_mul(u64::from(attempts))
+                .saturating_add(waits);
+            if lock.expiration_ms() < fetch_ms {
+                log::warn!("The lock expires before a fetch of up to {}ms", fetch_ms);
+                return Err(ClientError::InvalidConfiguration);
+            }
+        }
+
+        let mut client = Client {
+            endpoint: Endpoint::parse(endpoint)?,
+            chain_info: None,
+            ..Client::default()
+        };
+        client.set_relays(&self.relays.iter().map(String::as_str).collect::<Vec<_>>())?;
+        client.set_api_version(self.api_version);
+        client.set_transport(self.transport);
+        // as given for now, for the chain info to be fetched with them
+        for (endpoint, name, value) in &self.headers {
+            client.add_header(endpoint, name, value.clone())?;
+        }
+
+        let info = match (self.chain_info, &self.chain_hash) {
+            (Some(info), Some(hash)) if info.hash.as_slice() != hash.as_slice() => {
+                return Err(ClientError::ChainHashMismatch)
+            }
+            (Some(info), _) if !info.has_valid_hash() => {
+                return Err(ClientError::ChainHashMismatch)
+            }
+            (Some(info), _) => Some(info),
+            (None, Some(hash)) => match Network::by_chain_hash(hash) {
+                Some(network) => Some(network.info()),
+                None => Some(client.chain_info_of_with(transport, hash)?),
+            },
+            (None, None) => None,
+        };
+        match (&info, self.scheme) {
+            (Some(info), Some(scheme)) if info.scheme != scheme => {
+                log::warn!("The chain uses {:?}, not {:?}", info.scheme, scheme);
+                return Err(ClientError::InvalidConfiguration);
+            }
+            (None, _)
+                if self.scheme.is_some()
+                    || self.cache.is_some()
+                    || self.verification == Verification::Strict =>
+            {
+                return Err(ClientError::ChainNotConfigured);
+            }
+            _ => {}
+        }
+        // the v1 API serves the chains other than the `default` one under their chain hash
+        let chain_hash = info
+            .as_ref()
+            .filter(|info| {
+                !info.beacon_id.is_empty() && info.beacon_id.as_slice() != DEFAULT_BEACON_ID
+            })
+            .map(|info| vec_u8_to_hex(&info.hash));
+        if let (ApiVersion::V1, Some(hash)) = (self.api_version, &chain_hash) {
+            client.endpoint = under_chain_hash(&client.endpoint, hash)?;
+            for relay in client.relays.iter_mut() {
+                *relay = under_chain_hash(relay, hash)?;
+            }
+        }
+        client.headers = Default::default();
+        for (endpoint, name, value) in self.headers {
+            let endpoint = match endpoint.as_str() {
+                "" => String::new(),
+                url => configured_endpoint(&client, url, chain_hash.as_deref())?
+                    .as_str()
+                    .to_string(),
+            };
+            client.add_header(&endpoint, &name, value)?;
+        }
+        if let Some(user_agent) = &self.user_agent {
+            client.set_user_agent(user_agent)?;
+        }
+        client.chain_info = info;
+        client.set_cache(self.cache);
+        client.set_lock(self.lock);
+        client.set_stats(self.stats);
+        client.set_retry(self.retry);
+        client.set_verification(self.verification);
+        Ok(client)
+    }
+}
+
+/// The endpoint or relay of `client` a header of `url` is for: `url` itself, or the chain
+/// `chain_hash` under it (e.g. a relay's base URL, for a preset). Fails with
+/// `InvalidConfiguration` if it's neither, as the header would never be sent.
+fn configured_endpoint(
+    client: &Client,
+    url: &str,
+    chain_hash: Option<&str>,
+) -> Result<Endpoint, ClientError> {
+    let endpoint = Endpoint::parse(url)?;
+    let under_hash = match chain_hash {
+        Some(hash) => Some(under_chain_hash(&endpoint, hash)?),
+        None => None,
+    };
+    client
+        .endpoints()
+        .find(|configured| *configured == &endpoint || Some(*configured) == under_hash.as_ref())
+        .cloned()
+        .ok_or_else(|| {
+            log::warn!("Header of {}, which isn't an endpoint nor a relay", url);
+            ClientError::InvalidConfiguration
+        })
+}
+
+/// `endpoint` under the chain `hash`, unless it already is.
+fn under_chain_hash(endpoint: &Endpoint, hash: &str) -> Result<Endpoint, ClientError> {
+    match endpoint.base_path().rsplit('/').next() {
+        Some(last) if last == hash => Ok(endpoint.clone()),
+        _ => endpoint.join(hash),
+    }
+}
@@ -0,0 +1,132 @@
+//! Cache of verified rounds in the offchain worker local storage (PERSISTENT kind), so a node
+//! fetches each round over HTTP only once.
+//! Entries are keyed by chain hash and round number. An index of the cached round numbers (in
+//! insertion order) is kept per chain to bound the cache size, evicting the oldest entries first.
+
+use codec::{Decode, Encode, MaxEncodedLen};
+use serde::{Deserialize, Serialize};
+use sp_runtime::{
+    offchain::storage::{MutateStorageError, StorageRetrievalError, StorageValueRef},
+    RuntimeDebug,
+};
+use sp_std::vec::Vec;
+
+use crate::Round;
+
+/// Prefix of all the offchain storage keys written by the cache.
+pub const CACHE_KEY_PREFIX: &[u8] = b"drand-substrate-client::cache::";
+
+/// Configuration of the round cache, part of the `Client` configuration.
+#[derive(
+    Encode,
+    Decode,
+    Clone,
+    PartialEq,
+    Eq,
+    RuntimeDebug,
+    scale_info::TypeInfo,
+    MaxEncodedLen,
+    Serialize,
+    Deserialize,
+)]
+pub struct CacheConfig {
+    /// Maximum number of rounds kept per chain.
+    pub max_rounds: u32,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        CacheConfig { max_rounds: 128 }
+    }
+}
+
+/// View of the cached rounds of one chain.
+pub struct RoundCache<'a> {
+    chain_hash: &'a [u8],
+    config: &'a CacheConfig,
+}
+
+impl<'a> RoundCache<'a> {
+    pub fn new(chain_hash: &'a [u8], config: &'a CacheConfig) -> Self {
+        RoundCache { chain_hash, config }
+    }
+
+    /// Cached round `round`, if any.
+    pub fn get(&self, round: u64) -> Option<Round> {
+        let key = self.round_key(round);
+        StorageValueRef::persistent(&key).get().ok().flatten()
+    }
+
+    /// Caches a verified round, evicting the oldest entries beyond `max_rounds`.
+    /// The index is updated with a compare-and-set, again if another worker changed it meanwhile,
+    /// so concurrent inserts neither lose entries nor leak evicted ones.
+    pub fn insert(&self, round: &Round) {
+        let index_key = self.key(b"index");
+        let index_ref = StorageValueRef::persistent(&index_key);
+
+        let (index, evicted) = loop {
+            let mut evicted = Vec::new();
+            let updated =
+                index_ref.mutate(|index: Result<Option<Vec<u64>>, StorageRetrievalError>| {
+                    let mut index = index.ok().flatten().unwrap_or_default();
+                    if !index.contains(&round.round) {
+                        index.push(round.round);
+                    }
+                    let excess = index.len().saturating_sub(self.config.max_rounds as usize);
+                    evicted = index.drain(..excess).collect();
+                    Ok::<_, ()>(index)
+                });
+            match updated {
+                Ok(index) => break (index, evicted),
+                Err(MutateStorageError::ConcurrentModification(_)) => continue,
+                Err(MutateStorageError::ValueFunctionFailed(())) => return,
+            }
+        };
+
+        for evicted in evicted {
+            StorageValueRef::persistent(&self.round_key(evicted)).clear();
+        }
+        if index.contains(&round.round) {
+            StorageValueRef::persistent(&self.round_key(round.round)).set(round);
+        }
+    }
+
+    /// Latest round returned by the endpoint, recorded with `set_latest`.
+    pub fn latest(&self) -> Option<Round> {
+        let key = self.key(b"latest");
+        StorageValueRef::persistent(&key).get().ok().flatten()
+    }
+
+    /// Records the latest round, and caches it like any other round.
+    pub fn set_latest(&self, round: &Round) {
+        let key = self.key(b"latest");
+        StorageValueRef::persistent(&key).set(round);
+        self.insert(round);
+    }
+
+    /// Removes every cached round of the chain.
+    pub fn clear(&self) {
+        let index_key = self.key(b"index");
+        let mut index_ref = StorageValueRef::persistent(&index_key);
+        let index: Vec<u64> = index_ref.get().ok().flatten().unwrap_or_default();
+        for round in index {
+            StorageValueRef::persistent(&self.round_key(round)).clear();
+        }
+        index_ref.clear();
+        StorageValueRef::persistent(&self.key(b"latest")).clear();
+    }
+
+    fn round_key(&self, round: u64) -> Vec<u8> {
+        let mut key = self.key(b"round::");
+        key.extend(round.to_be_bytes());
+        key
+    }
+
+    fn key(&self, suffix: &[u8]) -> Vec<u8> {
+        let mut key = CACHE_KEY_PREFIX.to_vec();
+        key.extend(self.chain_hash);
+        key.extend(b"::");
+        key.extend(suffix);
+        key
+    }
+}
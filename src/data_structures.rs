@@ -7,16 +7,28 @@
 //! The SCALE-encodeable struct `T` can be directly constructed from the raw struct, since it
 //! implements `TryFrom<RawT>` trait.
 
+extern crate alloc;
+
+use alloc::{string::String, vec::Vec};
 use codec::{Decode, Encode, MaxEncodedLen};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use sha2::{Digest, Sha256};
 use sp_runtime::{traits::ConstU32, BoundedVec, RuntimeDebug};
 
-use crate::util::{hex_json_value_to_bounded_vec_u8, hex_to_vec_u8};
+use crate::{
+    util::{hex_json_value_to_bounded_vec_u8, hex_to_vec_u8},
+    MAINNET,
+};
+
+/// Maximum number of chains kept from a `/chains` response.
+pub const MAX_LISTED_CHAINS: u32 = 32;
 
+/// The `/chains` endpoint returns a JSON array of hex chain hashes.
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
 pub struct ChainsRaw {
-    pub hash: Value,
+    pub hashes: Vec<Value>,
 }
 
 /// This is should be returned from the `/chains` endpoint of a node.
@@ -24,19 +36,45 @@ pub struct ChainsRaw {
     Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, scale_info::TypeInfo, Serialize, Deserialize,
 )]
 pub struct Chains {
-    /// TODO use an array since it will always be a SHA2 hash (or at least a 32byte hash)
-    pub hash: BoundedVec<u8, ConstU32<32>>,
+    /// Hashes of the chains served by the node. Entries that aren't 32 bytes hex are dropped.
+    pub hashes: BoundedVec<BoundedVec<u8, ConstU32<32>>, ConstU32<MAX_LISTED_CHAINS>>,
 }
 
 impl From<ChainsRaw> for Chains {
     fn from(raw: ChainsRaw) -> Self {
+        let mut hashes: Vec<BoundedVec<u8, ConstU32<32>>> = raw
+            .hashes
+            .iter()
+            .filter_map(|hash| {
+                let hash = hash
+                    .as_str()
+                    .and_then(hex_to_vec_u8)
+                    .filter(|hash| hash.len() == 32)
+                    .and_then(|hash| hash.try_into().ok());
+                if hash.is_none() {
+                    log::warn!("Invalid chain hash in /chains");
+                }
+                hash
+            })
+            .collect();
+        if hashes.len() > MAX_LISTED_CHAINS as usize {
+            log::warn!("Only the first {} chains are kept", MAX_LISTED_CHAINS);
+            hashes.truncate(MAX_LISTED_CHAINS as usize);
+        }
+
         Self {
-            hash: hex_json_value_to_bounded_vec_u8::<32>(&raw.hash),
+            hashes: hashes.try_into().unwrap_or_default(),
         }
     }
 }
 
-/// TODO update these to support the latest drand API (eg schemeID)
+/// The `metadata` object of `/info`.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MetadataRaw {
+    #[serde(rename = "beaconID")]
+    pub beacon_id: String,
+}
+
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct InfoRaw {
     pub public_key: Value,
@@ -45,6 +83,89 @@ pub struct InfoRaw {
     pub hash: Value,
     #[serde(rename = "groupHash")]
     pub group_hash: Value,
+    /// Missing on old nodes, which only support `pedersen-bls-chained`.
+    #[serde(rename = "schemeID", default, skip_serializing_if = "Option::is_none")]
+    pub scheme_id: Option<String>,
+    /// Missing on old nodes, which only serve the `default` beacon.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<MetadataRaw>,
+}
+
+/// Signature scheme of a drand chain, as reported in the `schemeID` field of `/info`.
+#[derive(
+    Encode,
+    Decode,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    RuntimeDebug,
+    scale_info::TypeInfo,
+    MaxEncodedLen,
+    Serialize,
+    Deserialize,
+)]
+pub enum Scheme {
+    /// Public key on G1, signatures on G2 chained to the previous signature (LoE mainnet `default`).
+    #[default]
+    #[serde(rename = "pedersen-bls-chained")]
+    PedersenBlsChained,
+    /// Public key on G1, signatures on G2 over the round number only.
+    #[serde(rename = "pedersen-bls-unchained")]
+    PedersenBlsUnchained,
+    /// Public key on G2, signatures on G1 over the round number only, hashed to G1 with the
+    /// (non-standard) G2 domain separation tag.
+    #[serde(rename = "bls-unchained-on-g1")]
+    BlsUnchainedOnG1,
+    /// Public key on G2, signatures on G1 over the round number only, hashed to G1 as in
+    /// RFC 9380 (LoE mainnet `quicknet`).
+    #[serde(rename = "bls-unchained-g1-rfc9380")]
+    BlsUnchainedG1Rfc9380,
+    /// Public key on G2, signatures on G1 over the round number only, on the BN254 curve rather
+    /// than BLS12-381 (LoE mainnet `evmnet`). Its rounds can't be verified by this crate.
+    #[serde(rename = "bls-bn254-unchained-on-g1")]
+    BlsBn254UnchainedOnG1,
+}
+
+impl Scheme {
+    /// Parses a drand `schemeID`.
+    pub fn from_id(id: &str) -> Option<Scheme> {
+        match id {
+            "pedersen-bls-chained" => Some(Scheme::PedersenBlsChained),
+            "pedersen-bls-unchained" => Some(Scheme::PedersenBlsUnchained),
+            "bls-unchained-on-g1" => Some(Scheme::BlsUnchainedOnG1),
+            "bls-unchained-g1-rfc9380" => Some(Scheme::BlsUnchainedG1Rfc9380),
+            "bls-bn254-unchained-on-g1" => Some(Scheme::BlsBn254UnchainedOnG1),
+            _ => None,
+        }
+    }
+
+    /// The drand `schemeID`.
+    pub fn id(&self) -> &'static str {
+        match self {
+            Scheme::PedersenBlsChained => "pedersen-bls-chained",
+            Scheme::PedersenBlsUnchained => "pedersen-bls-unchained",
+            Scheme::BlsUnchainedOnG1 => "bls-unchained-on-g1",
+            Scheme::BlsUnchainedG1Rfc9380 => "bls-unchained-g1-rfc9380",
+            Scheme::BlsBn254UnchainedOnG1 => "bls-bn254-unchained-on-g1",
+        }
+    }
+
+    /// Whether each signature also covers the previous signature.
+    pub fn is_chained(&self) -> bool {
+        matches!(self, Scheme::PedersenBlsChained)
+    }
+
+    /// Whether the public key is on G2 (and signatures on G1).
+    pub fn public_key_on_g2(&self) -> bool {
+        matches!(
+            self,
+            Scheme::BlsUnchainedOnG1
+                | Scheme::BlsUnchainedG1Rfc9380
+                | Scheme::BlsBn254UnchainedOnG1
+        )
+    }
 }
 
 #[derive(
@@ -60,42 +181,137 @@ pub struct InfoRaw {
     Deserialize,
 )]
 pub struct Info {
-    pub public_key: BoundedVec<u8, ConstU32<48>>,
+    /// Compressed G1 (48 bytes) or G2 (96 bytes) point, depending on `scheme`, or uncompressed
+    /// BN254 G2 point (128 bytes).
+    pub public_key: BoundedVec<u8, ConstU32<128>>,
     pub period: u64,
     pub genesis_time: u64,
     pub hash: BoundedVec<u8, ConstU32<32>>,
     pub group_hash: BoundedVec<u8, ConstU32<32>>,
+    pub scheme: Scheme,
+    /// Name of the beacon on the network serving it, `default` for the LoE mainnet chain.
+    pub beacon_id: BoundedVec<u8, ConstU32<32>>,
 }
 
 impl Default for Info {
+    /// The League of Entropy mainnet `default` chain.
     fn default() -> Info {
-        Info {
-                public_key: hex_to_vec_u8("868f005eb8e6e4ca0a47c8a77ceaa5309a47978a7c71bc5cce96366b5d7a569937c529eeda66c7293784a9402801af31").unwrap().try_into().unwrap(),
-                period: 30,
-                genesis_time: 1595430000,
-                hash: hex_to_vec_u8("8990e7a9aaed2ffed73dbd7092123d6f289930540d7651336225dc172e51b2ce").unwrap().try_into().unwrap(),
-                group_hash: hex_to_vec_u8("176f93498eac9ca337150b46d21dd58673ea4e3581185f869672e59fa4cb390a").unwrap().try_into().unwrap(),
-            }
+        MAINNET.info()
+    }
+}
+
+/// Beacon id of the LoE mainnet chain, and of every chain of old nodes.
+pub const DEFAULT_BEACON_ID: &[u8] = b"default";
+
+impl Info {
+    /// The chain hash as drand computes it: `sha256` of the period (u32 BE), the genesis time
+    /// (i64 BE), the public key and the group hash, followed by the beacon id unless it's the
+    /// default one (`default`). The scheme isn't part of the hash: it is only checked by
+    /// verifying rounds, whose signatures don't verify under another scheme.
+    pub fn compute_hash(&self) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update((self.period as u32).to_be_bytes());
+        hasher.update((self.genesis_time as i64).to_be_bytes());
+        hasher.update(&self.public_key);
+        hasher.update(&self.group_hash);
+        if !self.beacon_id.is_empty() && self.beacon_id.as_slice() != DEFAULT_BEACON_ID {
+            hasher.update(&self.beacon_id);
+        }
+        hasher.finalize().into()
+    }
+
+    /// Whether `hash` is the hash of the rest of the chain info, i.e. the chain info is the one
+    /// identified by `hash`.
+    pub fn has_valid_hash(&self) -> bool {
+        self.hash.as_slice() == self.compute_hash()
+    }
+
+    /// Round emitted at `timestamp` (unix seconds), i.e. the latest round available at that time.
+    /// Round 1 is emitted at `genesis_time`, so any earlier timestamp maps to round 0.
+    pub fn round_at(&self, timestamp: u64) -> u64 {
+        if timestamp < self.genesis_time {
+            return 0;
+        }
+        (timestamp - self.genesis_time) / self.period.max(1) + 1
+    }
+
+    /// Unix time (in seconds) at which `round` is expected to be emitted.
+    pub fn round_time(&self, round: u64) -> u64 {
+        self.genesis_time
+            .saturating_add(round.saturating_sub(1).saturating_mul(self.period))
     }
 }
 
 impl From<InfoRaw> for Info {
     fn from(info: InfoRaw) -> Self {
+        let beacon_id = info
+            .metadata
+            .map(|metadata| metadata.beacon_id)
+            .unwrap_or_else(|| String::from("default"));
+
         Info {
-            public_key: hex_json_value_to_bounded_vec_u8::<48>(&info.public_key),
+            public_key: hex_json_value_to_bounded_vec_u8::<128>(&info.public_key),
             period: info.period,
             genesis_time: info.genesis_time,
             hash: hex_json_value_to_bounded_vec_u8::<32>(&info.hash),
             group_hash: hex_json_value_to_bounded_vec_u8::<32>(&info.group_hash),
+            scheme: parse_scheme(info.scheme_id.as_deref()),
+            beacon_id: bounded_beacon_id(beacon_id),
         }
     }
 }
 
+/// InfoV2Raw is the chain info returned by the v2 API.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InfoV2Raw {
+    pub public_key: Value,
+    pub period: u64,
+    pub genesis_time: u64,
+    /// The group hash of the v1 API.
+    pub genesis_seed: Value,
+    pub chain_hash: Value,
+    pub scheme: String,
+    pub beacon_id: String,
+}
+
+impl From<InfoV2Raw> for Info {
+    fn from(info: InfoV2Raw) -> Self {
+        Info {
+            public_key: hex_json_value_to_bounded_vec_u8::<128>(&info.public_key),
+            period: info.period,
+            genesis_time: info.genesis_time,
+            hash: hex_json_value_to_bounded_vec_u8::<32>(&info.chain_hash),
+            group_hash: hex_json_value_to_bounded_vec_u8::<32>(&info.genesis_seed),
+            scheme: parse_scheme(Some(&info.scheme)),
+            beacon_id: bounded_beacon_id(info.beacon_id),
+        }
+    }
+}
+
+fn parse_scheme(id: Option<&str>) -> Scheme {
+    match id {
+        None => Scheme::default(),
+        Some(id) => Scheme::from_id(id).unwrap_or_else(|| {
+            log::warn!("Unknown scheme {}, assuming the default one", id);
+            Scheme::default()
+        }),
+    }
+}
+
+fn bounded_beacon_id(beacon_id: String) -> BoundedVec<u8, ConstU32<32>> {
+    beacon_id.into_bytes().try_into().unwrap_or_else(|_| {
+        log::warn!("Beacon id longer than 32 bytes");
+        Default::default()
+    })
+}
+
 /// RoundRaw is used for http interactions and JSON parsing with serde.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct RoundRaw {
     pub round: u64,
     pub randomness: Value,
+    /// Missing for unchained schemes.
+    #[serde(default)]
     pub previous_signature: Value,
     pub signature: Value,
 }
@@ -119,7 +335,9 @@ pub struct Round {
     pub round: u64,
     // TODO - use array instead of BoundedVec
     pub randomness: BoundedVec<u8, ConstU32<32>>,
+    /// Empty for unchained schemes.
     pub previous_signature: BoundedVec<u8, ConstU32<96>>,
+    /// Compressed G2 (96 bytes) or G1 (48 bytes) point, depending on the chain's scheme.
     pub signature: BoundedVec<u8, ConstU32<96>>,
 }
 
@@ -141,3 +359,28 @@ impl From<RoundRaw> for Round {
         }
     }
 }
+
+/// RoundV2Raw is the round returned by the v2 API, without the randomness, which is the
+/// `sha256` of the signature.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RoundV2Raw {
+    pub round: u64,
+    pub signature: Value,
+    /// Missing for unchained schemes.
+    #[serde(default)]
+    pub previous_signature: Value,
+}
+
+impl From<RoundV2Raw> for Round {
+    fn from(round: RoundV2Raw) -> Self {
+        let signature = hex_json_value_to_bounded_vec_u8::<96>(&round.signature);
+        let randomness: [u8; 32] = Sha256::digest(&signature).into();
+
+        Round {
+            round: round.round,
+            randomness: randomness.to_vec().try_into().unwrap_or_default(),
+            previous_signature: hex_json_value_to_bounded_vec_u8::<96>(&round.previous_signature),
+            signature,
+        }
+    }
+}
@@ -0,0 +1,74 @@
+//! Per-purpose derivation of the randomness of a round, so that pallets consuming the same round
+//! get independent values, and receipts recording each derivation for audit.
+//!
+//! The output for a context and an index is
+//! `sha256("drand-substrate-client::derive" || len(context) as u32 BE || context || index as u64
+//! BE || randomness)`. The context names the consumer (e.g. `b"my-pallet::lottery"`), the index
+//! tells apart several values drawn by the same consumer from one round.
+
+use codec::{Decode, Encode, MaxEncodedLen};
+use sha2::{Digest, Sha256};
+use sp_runtime::{traits::ConstU32, BoundedVec, RuntimeDebug};
+
+use crate::Round;
+
+/// Domain separation tag of the derivation.
+pub const DERIVE_DOMAIN: &[u8] = b"drand-substrate-client::derive";
+
+/// Maximum length of a context recorded in a `RandomnessReceipt`.
+pub const MAX_CONTEXT_LEN: u32 = 128;
+
+impl Round {
+    /// Randomness of this round derived for `context` and `index`.
+    pub fn derive(&self, context: &[u8], index: u64) -> [u8; 32] {
+        derive(&self.randomness, context, index)
+    }
+
+    /// Derives the randomness for `context` and `index`, and records it in a receipt.
+    /// Returns `None` if the context is longer than `MAX_CONTEXT_LEN`.
+    pub fn receipt(
+        &self,
+        chain_hash: &[u8],
+        context: &[u8],
+        index: u64,
+    ) -> Option<RandomnessReceipt> {
+        Some(RandomnessReceipt {
+            chain_hash: chain_hash.to_vec().try_into().ok()?,
+            round: self.round,
+            context: context.to_vec().try_into().ok()?,
+            index,
+            output: self.derive(context, index),
+        })
+    }
+}
+
+/// Record of a derivation: anyone can fetch round `round` of the chain `chain_hash` from the
+/// public beacon, verify it, and check the output with `RandomnessReceipt::check`.
+#[derive(
+    Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, scale_info::TypeInfo, MaxEncodedLen,
+)]
+pub struct RandomnessReceipt {
+    pub chain_hash: BoundedVec<u8, ConstU32<32>>,
+    pub round: u64,
+    pub context: BoundedVec<u8, ConstU32<MAX_CONTEXT_LEN>>,
+    pub index: u64,
+    pub output: [u8; 32],
+}
+
+impl RandomnessReceipt {
+    /// Checks the receipt against `round`, which should have been verified against the chain
+    /// `chain_hash` first (e.g. with `Client::verify_round`).
+    pub fn check(&self, round: &Round) -> bool {
+        self.round == round.round && self.output == round.derive(&self.context, self.index)
+    }
+}
+
+fn derive(randomness: &[u8], context: &[u8], index: u64) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(DERIVE_DOMAIN);
+    hasher.update((context.len() as u32).to_be_bytes());
+    hasher.update(context);
+    hasher.update(index.to_be_bytes());
+    hasher.update(randomness);
+    hasher.finalize().into()
+}
@@ -0,0 +1,230 @@
+//! Base URLs of drand relays, e.g. `https://api.drand.sh` or `https://relay.example.com:8443/drand`.
+//! An `Endpoint` is parsed and validated once, when it's created or decoded, rather than every
+//! time a request is made:
+//! - the scheme is `http` or `https`;
+//! - the host is a domain name, or an IPv4 or bracketed IPv6 address, without credentials
+//!   (send them as headers instead, with `Client::add_header`);
+//! - the port, if any, is a non-zero `u16`;
+//! - the base path, if any, has no query or fragment.
+//!
+//! The scheme and host are lowercased and trailing slashes are trimmed, so equal endpoints
+//! compare (and key their offchain records) the same.
+
+extern crate alloc;
+
+use alloc::{
+    format,
+    string::{String, ToString},
+};
+use codec::{Decode, Encode, Input, MaxEncodedLen};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use sp_runtime::{traits::ConstU32, BoundedVec, RuntimeDebug};
+use sp_std::str;
+
+use crate::ClientError;
+
+/// Maximum length of an endpoint URL, in bytes.
+pub const MAX_ENDPOINT_LEN: u32 = 256;
+
+/// Validated base URL of a drand relay.
+#[derive(Encode, Clone, PartialEq, Eq, RuntimeDebug, scale_info::TypeInfo, MaxEncodedLen)]
+pub struct Endpoint(BoundedVec<u8, ConstU32<MAX_ENDPOINT_LEN>>);
+
+impl Endpoint {
+    /// Parses and normalizes `url`.
+    pub fn parse(url: &str) -> Result<Endpoint, ClientError> {
+        let invalid = |reason: &str| {
+            log::warn!("Invalid endpoint {}: {}", url, reason);
+            ClientError::InvalidEndpoint
+        };
+
+        let (scheme, rest) = url
+            .trim()
+            .split_once("://")
+            .ok_or_else(|| invalid("no scheme"))?;
+        let scheme = scheme.to_ascii_lowercase();
+        if scheme != "http" && scheme != "https" {
+            return Err(invalid("unsupported scheme"));
+        }
+
+        let (authority, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+        if authority.contains('@') {
+            return Err(invalid("credentials in URL"));
+        }
+        let (host, port) = split_port(authority).ok_or_else(|| invalid("invalid port"))?;
+        if !is_valid_host(host) {
+            return Err(invalid("invalid host"));
+        }
+
+        let path = path.trim_end_matches('/');
+        if !path.bytes().all(is_path_byte) || path.contains("//") {
+            return Err(invalid("invalid base path"));
+        }
+
+        let mut normalized = format!("{scheme}://{}", host.to_ascii_lowercase());
+        if let Some(port) = port {
+            normalized.push_str(&format!(":{port}"));
+        }
+        normalized.push_str(path);
+        normalized
+            .into_bytes()
+            .try_into()
+            .map(Endpoint)
+            .map_err(|_| invalid("too long"))
+    }
+
+    /// The normalized URL.
+    pub fn as_str(&self) -> &str {
+        // only ever built from a `str`, in `parse`
+        str::from_utf8(&self.0).unwrap_or_default()
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// `http` or `https`.
+    pub fn scheme(&self) -> &str {
+        self.split().0
+    }
+
+    /// Domain name or address, with brackets for an IPv6 address.
+    pub fn host(&self) -> &str {
+        let (_, authority, _) = self.split();
+        split_port(authority).map_or(authority, |(host, _)| host)
+    }
+
+    pub fn port(&self) -> Option<u16> {
+        let (_, authority, _) = self.split();
+        split_port(authority).and_then(|(_, port)| port)
+    }
+
+    /// Path under which the API is served, without a trailing slash. Empty if served at the root.
+    pub fn base_path(&self) -> &str {
+        self.split().2
+    }
+
+    /// URL of `path` (e.g. `/info`) relative to the endpoint.
+    pub fn url(&self, path: &str) -> String {
+        format!("{}{path}", self.as_str())
+    }
+
+    /// The endpoint with `segment` appended to its base path, e.g. a chain hash.
+    pub fn join(&self, segment: &str) -> Result<Endpoint, ClientError> {
+        Endpoint::parse(&format!("{}/{}", self.as_str(), segment.trim_matches('/')))
+    }
+
+    /// Scheme, authority and base path.
+    fn split(&self) -> (&str, &str, &str) {
+        let url = self.as_str();
+        let (scheme, rest) = url.split_once("://").unwrap_or(("", url));
+        let (authority, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+        (scheme, authority, path)
+    }
+}
+
+/// Host and port of `authority`, or `None` if the port is invalid.
+fn split_port(authority: &str) -> Option<(&str, Option<u16>)> {
+    let port_at = match authority.rfind(']') {
+        Some(end) => authority[end..].find(':').map(|at| end + at),
+        None => authority.rfind(':'),
+    };
+    match port_at {
+        Some(at) => {
+            let port = &authority[at + 1..];
+            if !port.bytes().all(|b| b.is_ascii_digit()) {
+                return None;
+            }
+            let port: u16 = port.parse().ok().filter(|port| *port != 0)?;
+            Some((&authority[..at], Some(port)))
+        }
+        None => Some((authority, None)),
+    }
+}
+
+/// A domain name (or IPv4 address), or a bracketed IPv6 address.
+fn is_valid_host(host: &str) -> bool {
+    if let Some(address) = host.strip_prefix('[').and_then(|h| h.strip_suffix(']')) {
+        return address.contains(':')
+            && address
+                .bytes()
+                .all(|b| b.is_ascii_hexdigit() || b == b':' || b == b'.');
+    }
+    !host.is_empty()
+        && host.split('.').all(|label| {
+            !label.is_empty()
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label
+                    .bytes()
+                    .all(|b| b.is_ascii_alphanumeric() || b == b'-')
+        })
+}
+
+/// Unreserved and sub-delimiter characters, percent-encodings, `:`, `@` and `/`.
+fn is_path_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b"-._~!$&'()*+,;=%:@/".contains(&b)
+}
+
+impl Decode for Endpoint {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, codec::Error> {
+        let url = BoundedVec::<u8, ConstU32<MAX_ENDPOINT_LEN>>::decode(input)?;
+        str::from_utf8(&url)
+            .ok()
+            .and_then(|url| Endpoint::parse(url).ok())
+            .ok_or_else(|| "Invalid endpoint".into())
+    }
+}
+
+/// Serialized as the URL.
+impl Serialize for Endpoint {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Endpoint {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let url = String::deserialize(deserializer)?;
+        Endpoint::parse(&url).map_err(|_| de::Error::custom("invalid endpoint"))
+    }
+}
+
+impl TryFrom<&str> for Endpoint {
+    type Error = ClientError;
+
+    fn try_from(url: &str) -> Result<Self, Self::Error> {
+        Endpoint::parse(url)
+    }
+}
+
+impl TryFrom<String> for Endpoint {
+    type Error = ClientError;
+
+    fn try_from(url: String) -> Result<Self, Self::Error> {
+        Endpoint::parse(&url)
+    }
+}
+
+impl From<Endpoint> for String {
+    fn from(endpoint: Endpoint) -> String {
+        endpoint.as_str().to_string()
+    }
+}
+
+impl AsRef<[u8]> for Endpoint {
+    fn as_ref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+#[test]
+fn test_split_port() {
+    assert_eq!(split_port("relay:8080"), Some(("relay", Some(8080))));
+    assert_eq!(split_port("relay"), Some(("relay", None)));
+    assert_eq!(split_port("[::1]:443"), Some(("[::1]", Some(443))));
+    assert_eq!(split_port("[::1]"), Some(("[::1]", None)));
+    assert_eq!(split_port("relay:0"), None);
+    assert_eq!(split_port("relay:65536"), None);
+    assert_eq!(split_port("relay:+80"), None);
+}
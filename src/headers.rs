@@ -0,0 +1,179 @@
+//! HTTP headers sent with the requests of a `Client`, e.g. an `Authorization` header for relays
+//! behind an API gateway, or an identifying `User-Agent` for public relays.
+//! Headers are configured per endpoint, as part of the `Client` configuration, which is public
+//! once stored on-chain. So the value of a header can instead be a secret, read from the offchain
+//! local storage (PERSISTENT kind) of each node, where the operator puts it with the
+//! `offchain_localStorageSet` RPC. A secret is read as is, not SCALE-encoded.
+
+extern crate alloc;
+
+use alloc::string::String;
+use codec::{Decode, Encode, MaxEncodedLen};
+use serde::{Deserialize, Serialize};
+use sp_runtime::{offchain::StorageKind, traits::ConstU32, BoundedVec, RuntimeDebug};
+use sp_std::vec::Vec;
+
+use crate::{Client, ClientError, Endpoint, MAX_RELAYS};
+
+/// Maximum number of headers per endpoint.
+pub const MAX_HEADERS: u32 = 8;
+
+/// Value of a header.
+#[derive(
+    Encode,
+    Decode,
+    Clone,
+    PartialEq,
+    Eq,
+    RuntimeDebug,
+    scale_info::TypeInfo,
+    MaxEncodedLen,
+    Serialize,
+    Deserialize,
+)]
+pub enum HeaderValue {
+    /// The value itself, public with the client configuration.
+    Plain(BoundedVec<u8, ConstU32<256>>),
+    /// Key of the value in the offchain local storage.
+    Secret(BoundedVec<u8, ConstU32<64>>),
+}
+
+/// A header sent with requests.
+#[derive(
+    Encode,
+    Decode,
+    Clone,
+    PartialEq,
+    Eq,
+    RuntimeDebug,
+    scale_info::TypeInfo,
+    MaxEncodedLen,
+    Serialize,
+    Deserialize,
+)]
+pub struct HttpHeader {
+    pub name: BoundedVec<u8, ConstU32<64>>,
+    pub value: HeaderValue,
+}
+
+/// Headers sent with every request to an endpoint.
+#[derive(
+    Encode,
+    Decode,
+    Clone,
+    PartialEq,
+    Eq,
+    RuntimeDebug,
+    scale_info::TypeInfo,
+    MaxEncodedLen,
+    Serialize,
+    Deserialize,
+)]
+pub struct EndpointHeaders {
+    /// The endpoint (`endpoint` or one of the relays). `None` for every endpoint.
+    pub endpoint: Option<Endpoint>,
+    pub headers: BoundedVec<HttpHeader, ConstU32<MAX_HEADERS>>,
+}
+
+/// Header configurations of a `Client`: one per relay and `endpoint`, and one for all of them.
+pub type ClientHeaders = BoundedVec<EndpointHeaders, ConstU32<{ MAX_RELAYS + 2 }>>;
+
+impl Client {
+    /// Adds a header to the requests to `endpoint`, or to every endpoint if it's empty.
+    /// Fails with `InvalidEndpoint` if `endpoint` isn't empty nor a valid `Endpoint`.
+    pub fn add_header(
+        &mut self,
+        endpoint: &str,
+        name: &str,
+        value: HeaderValue,
+    ) -> Result<(), ClientError> {
+        let endpoint = match endpoint {
+            "" => None,
+            endpoint => Some(Endpoint::parse(endpoint)?),
+        };
+        let header = HttpHeader {
+            name: name
+                .as_bytes()
+                .to_vec()
+                .try_into()
+                .map_err(|_| ClientError::Misc)?,
+            value,
+        };
+
+        match self
+            .headers
+            .iter_mut()
+            .find(|headers| headers.endpoint == endpoint)
+        {
+            Some(headers) => headers
+                .headers
+                .try_push(header)
+                .map_err(|_| ClientError::Misc),
+            None => {
+                let headers = EndpointHeaders {
+                    endpoint,
+                    headers: sp_std::vec![header]
+                        .try_into()
+                        .map_err(|_| ClientError::Misc)?,
+                };
+                self.headers
+                    .try_push(headers)
+                    .map_err(|_| ClientError::Misc)
+            }
+        }
+    }
+
+    /// Sends `user_agent` as the `User-Agent` of every request, replacing any previous one.
+    pub fn set_user_agent(&mut self, user_agent: &str) -> Result<(), ClientError> {
+        let value = user_agent
+            .as_bytes()
+            .to_vec()
+            .try_into()
+            .map_err(|_| ClientError::Misc)?;
+        for headers in self
+            .headers
+            .iter_mut()
+            .filter(|headers| headers.endpoint.is_none())
+        {
+            headers
+                .headers
+                .retain(|header| !header.name.eq_ignore_ascii_case(b"user-agent"));
+        }
+        self.add_header("", "User-Agent", HeaderValue::Plain(value))
+    }
+
+    /// Headers of a request to `endpoint`, with the secrets read from the offchain local
+    /// storage. Headers whose secret is missing, or that aren't UTF-8, are left out.
+    pub(crate) fn request_headers(&self) -> Vec<(String, String)> {
+        self.resolve_headers(|key| sp_io::offchain::local_storage_get(StorageKind::PERSISTENT, key))
+    }
+
+    /// Headers of a request to `endpoint`, with the secrets read from `secret`.
+    pub(crate) fn resolve_headers(
+        &self,
+        secret: impl Fn(&[u8]) -> Option<Vec<u8>>,
+    ) -> Vec<(String, String)> {
+        self.headers
+            .iter()
+            .filter(|headers| match &headers.endpoint {
+                Some(endpoint) => endpoint == &self.endpoint,
+                None => true,
+            })
+            .flat_map(|headers| headers.headers.iter())
+            .filter_map(|header| {
+                let value = match &header.value {
+                    HeaderValue::Plain(value) => value.to_vec(),
+                    HeaderValue::Secret(key) => match secret(key) {
+                        Some(value) => value,
+                        None => {
+                            log::warn!("Missing secret of header {:?}", header.name);
+                            return None;
+                        }
+                    },
+                };
+                let name = String::from_utf8(header.name.to_vec()).ok()?;
+                Some((name, String::from_utf8(value).ok()?))
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,155 @@
+//! Health of drand relays, from their `/health` endpoint, and selection of a relay that is in
+//! sync with the chain.
+//! A relay answers `/health` (or `/{chain hash}/health`) with the last round it has seen and the
+//! round expected at the current time, and a non-`200` status code when it is behind. Health
+//! checks are requested like the other requests of the `Client` (headers, redirects and
+//! statistics) but aren't retried, and are recorded in the offchain local storage (PERSISTENT kind), so a relay is checked at
+//! most once every `HEALTH_RECHECK_MS` across offchain worker runs.
+
+use codec::{Decode, Encode, MaxEncodedLen};
+use serde::{Deserialize, Serialize};
+use sp_runtime::{offchain::storage::StorageValueRef, RuntimeDebug};
+use sp_std::vec::Vec;
+
+use crate::{Client, ClientError, Endpoint, Transport};
+
+/// Prefix of all the offchain storage keys of the health records.
+pub const HEALTH_KEY_PREFIX: &[u8] = b"drand-substrate-client::health::";
+
+/// How long a health check is trusted before the relay is checked again.
+pub const HEALTH_RECHECK_MS: u64 = 30_000;
+
+/// Maximum number of relays of a `Client`, besides its `endpoint`.
+pub const MAX_RELAYS: u32 = 8;
+
+/// HealthRaw is used for http interactions and JSON parsing with serde.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HealthRaw {
+    pub current: u64,
+    pub expected: u64,
+}
+
+/// Sync state of a relay.
+#[derive(
+    Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, scale_info::TypeInfo, MaxEncodedLen,
+)]
+pub struct Health {
+    /// Last round seen by the relay.
+    pub current: u64,
+    /// Round expected at the current time.
+    pub expected: u64,
+}
+
+impl Health {
+    /// Whether the relay has the expected round, or the one before (which may still be in
+    /// flight), as drand itself considers it.
+    pub fn is_synced(&self) -> bool {
+        self.current.saturating_add(1) >= self.expected
+    }
+}
+
+impl From<HealthRaw> for Health {
+    fn from(raw: HealthRaw) -> Self {
+        Health {
+            current: raw.current,
+            expected: raw.expected,
+        }
+    }
+}
+
+/// Last health check of an endpoint.
+#[derive(
+    Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, scale_info::TypeInfo, MaxEncodedLen,
+)]
+pub struct HealthRecord {
+    /// `None` if the relay couldn't be reached, or answered something else than a health status.
+    pub health: Option<Health>,
+    /// Unix time (in milliseconds) of the check.
+    pub checked_at: u64,
+}
+
+impl HealthRecord {
+    pub fn is_healthy(&self) -> bool {
+        matches!(&self.health, Some(health) if health.is_synced())
+    }
+
+    /// Recorded health check of `endpoint`, if any.
+    pub fn get(endpoint: &[u8]) -> Option<HealthRecord> {
+        StorageValueRef::persistent(&health_key(endpoint))
+            .get()
+            .ok()
+            .flatten()
+    }
+
+    fn set(&self, endpoint: &[u8]) {
+        StorageValueRef::persistent(&health_key(endpoint)).set(self);
+    }
+}
+
+impl Client {
+    /// HTTP GET on the endpoint's `/health`.
+    pub fn health(&self) -> Result<Health, ClientError> {
+        self.health_with(&self.transport)
+    }
+
+    /// Same as `health`, fetching through the given transport.
+    /// The status code is ignored: relays that are behind answer with an error status and the
+    /// same body. So the check is a single attempt, whatever the `retry` settings.
+    pub fn health_with<T: Transport>(&self, transport: &T) -> Result<Health, ClientError> {
+        let response = self.fetch_once_with(transport, &self.endpoint.url("/health"), &[])?;
+        let health_raw: HealthRaw = Client::parse_json(&response.body)?;
+        Ok(Health::from(health_raw))
+    }
+
+    /// Sets the relays tried after `endpoint` by `select_endpoint`.
+    pub fn set_relays(&mut self, relays: &[&str]) -> Result<(), ClientError> {
+        let relays: Vec<_> = relays
+            .iter()
+            .map(|relay| Endpoint::parse(relay))
+            .collect::<Result<_, _>>()?;
+        self.relays = relays.try_into().map_err(|_| ClientError::Misc)?;
+        Ok(())
+    }
+
+    /// A client using the first of `endpoint` and `relays` that is in sync with the chain.
+    /// Health checks older than `HEALTH_RECHECK_MS` are done again, and recorded.
+    pub fn select_endpoint(&self) -> Result<Client, ClientError> {
+        self.select_endpoint_with(&self.transport)
+    }
+
+    /// Same as `select_endpoint`, fetching through the given transport.
+    pub fn select_endpoint_with<T: Transport>(&self, transport: &T) -> Result<Client, ClientError> {
+        let now = sp_io::offchain::timestamp().unix_millis();
+
+        let endpoints = sp_std::iter::once(&self.endpoint).chain(self.relays.iter());
+        for endpoint in endpoints {
+            let client = Client {
+                endpoint: endpoint.clone(),
+                ..self.clone()
+            };
+            let record = HealthRecord::get(endpoint.as_bytes())
+                .filter(|record| now < record.checked_at.saturating_add(HEALTH_RECHECK_MS))
+                .unwrap_or_else(|| {
+                    let record = HealthRecord {
+                        health: client.health_with(transport).ok(),
+                        checked_at: now,
+                    };
+                    record.set(endpoint.as_bytes());
+                    record
+                });
+
+            if record.is_healthy() {
+                return Ok(client);
+            }
+            log::warn!("Skipping unhealthy endpoint: {:?}", record.health);
+        }
+
+        Err(ClientError::NoHealthyEndpoint)
+    }
+}
+
+fn health_key(endpoint: &[u8]) -> Vec<u8> {
+    let mut key = HEALTH_KEY_PREFIX.to_vec();
+    key.extend(endpoint);
+    key
+}
@@ -0,0 +1,369 @@
+//! HTTP semantics on top of the transports: redirects, and freshness of cached responses.
+//! Relays move (e.g. to a new domain), answering with a redirect, and CDNs in front of relays
+//! serve cached responses, which for `/public/latest` may lag behind the chain.
+//! - Redirects are followed, at most `MAX_REDIRECTS` times and only to the same scheme, so an
+//!   `https` endpoint is never downgraded. The configured headers of the endpoint (which may hold
+//!   secrets) are only sent to its own origin.
+//! - A `latest` response served from a cache (with a non-zero `Age`) is stale if it's older than
+//!   its `Cache-Control` `max-age`, or if its round is behind the round expected when it was
+//!   served (its `Date` plus its `Age`). A stale response is requested again with
+//!   `Cache-Control: no-cache`.
+
+extern crate alloc;
+
+use alloc::{
+    format,
+    string::{String, ToString},
+};
+use sp_runtime::RuntimeDebug;
+use sp_std::vec::Vec;
+
+use crate::{
+    api::ApiPath, retry::is_endpoint_failure, Client, ClientError, HttpResponse, Info, Round,
+    Transport,
+};
+
+/// Maximum number of redirects followed for a request.
+pub const MAX_REDIRECTS: u32 = 5;
+
+/// How old a response is, and how old it may get, from its headers.
+#[derive(Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct Freshness {
+    /// Time spent in caches, in seconds, from the `Age` header. `0` if not from a cache.
+    pub age: u64,
+    /// Unix time (in seconds) the response was generated at, from the `Date` header.
+    pub date: Option<u64>,
+    /// From the `max-age` (or `s-maxage`) directive of the `Cache-Control` header.
+    pub max_age: Option<u64>,
+}
+
+impl Freshness {
+    pub fn of(response: &HttpResponse) -> Freshness {
+        let age = response
+            .header("age")
+            .and_then(|age| age.trim().parse().ok())
+            .unwrap_or_default();
+        let date = response.header("date").and_then(parse_http_date);
+        let max_age = response.header("cache-control").and_then(|cache_control| {
+            cache_control
+                .split(',')
+                .filter_map(|directive| directive.trim().split_once('='))
+                .filter(|(name, _)| matches!(name.trim(), "max-age" | "s-maxage"))
+                .filter_map(|(_, value)| value.trim().parse().ok())
+                .min()
+        });
+        Freshness { age, date, max_age }
+    }
+
+    /// Whether a `latest` response with this freshness, holding `round` of the chain `info`, is
+    /// fresh enough: either it's not from a cache, or it's within its `max-age` and its round is
+    /// the one expected when it was served, i.e. at `date + age` (or the one before, which may
+    /// still be in flight). This relies on the clock of the server rather than on ours.
+    pub fn is_fresh(&self, info: &Info, round: u64) -> bool {
+        if self.age == 0 {
+            return true;
+        }
+        if matches!(self.max_age, Some(max_age) if self.age > max_age) {
+            return false;
+        }
+        match self.date {
+            Some(date) => round.saturating_add(1) >= info.round_at(date.saturating_add(self.age)),
+            None => true,
+        }
+    }
+}
+
+impl Client {
+    /// HTTP GET on `url`, following redirects, with the configured headers of `endpoint` and
+    /// `extra_headers`. Returns the final response, if it's a `200`. With `stats` and `retry`
+    /// enabled, the request is recorded, and retried as a whole, as failed only if the endpoint
+    /// failed (see `retry`).
+    pub(crate) fn fetch_with<T: Transport>(
+        &self,
+        transport: &T,
+        url: &str,
+        extra_headers: &[(String, String)],
+    ) -> Result<HttpResponse, ClientError> {
+        let response = self.fetch_response_with(transport, url, extra_headers)?;
+        if response.code != 200 {
+            log::warn!("Unexpected status code: {}", response.code);
+            return Err(ClientError::Http);
+        }
+        Ok(response)
+    }
+
+    /// Same as `fetch_with`, returning the final response whatever its status code.
+    pub(crate) fn fetch_response_with<T: Transport>(
+        &self,
+        transport: &T,
+        url: &str,
+        extra_headers: &[(String, String)],
+    ) -> Result<HttpResponse, ClientError> {
+        let headers = self.request_headers();
+        self.with_retries(|| self.attempt(transport, url, &headers, extra_headers))
+    }
+
+    /// Same as `fetch_response_with`, with a single attempt whatever the `retry` settings, e.g.
+    /// for probes.
+    pub(crate) fn fetch_once_with<T: Transport>(
+        &self,
+        transport: &T,
+        url: &str,
+        extra_headers: &[(String, String)],
+    ) -> Result<HttpResponse, ClientError> {
+        self.attempt(transport, url, &self.request_headers(), extra_headers)
+    }
+
+    /// One attempt of a request, recorded with `stats` enabled.
+    fn attempt<T: Transport>(
+        &self,
+        transport: &T,
+        url: &str,
+        headers: &[(String, String)],
+        extra_headers: &[(String, String)],
+    ) -> Result<HttpResponse, ClientError> {
+        let started = self
+            .stats
+            .as_ref()
+            .map(|_| sp_io::offchain::timestamp().unix_millis());
+        let result = follow_redirects(transport, url, headers, extra_headers);
+        if let Some(started) = started {
+            self.record_request(started, !is_endpoint_failure(&result));
+        }
+        result
+    }
+
+    /// Fetches the latest round, again without caches if the response was stale.
+    pub(crate) fn fetch_latest<T: Transport>(&self, transport: &T) -> Result<Round, ClientError> {
+        let url = String::from_utf8(self.url(&self.path(ApiPath::Latest)))
+            .map_err(|_| ClientError::Http)?;
+
+        let response = self.fetch_with(transport, &url, &[])?;
+        let round = self.parse_round(&response.body)?;
+        if self.is_fresh(&response, &round) {
+            return Ok(round);
+        }
+
+        log::warn!("Stale latest round {}, fetching it again", round.round);
+        let no_cache = [("Cache-Control".to_string(), "no-cache".to_string())];
+        let response = self.fetch_with(transport, &url, &no_cache)?;
+        let round = self.parse_round(&response.body)?;
+        if self.is_fresh(&response, &round) {
+            Ok(round)
+        } else {
+            Err(ClientError::StaleResponse)
+        }
+    }
+
+    /// Whether a `latest` response is fresh enough. Without `chain_info` to know the expected
+    /// round, it is taken as fresh.
+    fn is_fresh(&self, response: &HttpResponse, round: &Round) -> bool {
+        match &self.chain_info {
+            Some(info) => Freshness::of(response).is_fresh(info, round.round),
+            None => true,
+        }
+    }
+}
+
+/// GET on `url`, following redirects, up to a response that isn't one. `headers` are only sent to
+/// the origin of `url`.
+fn follow_redirects<T: Transport>(
+    transport: &T,
+    url: &str,
+    headers: &[(String, String)],
+    extra_headers: &[(String, String)],
+) -> Result<HttpResponse, ClientError> {
+    let mut target = url.to_string();
+    for _ in 0..=MAX_REDIRECTS {
+        let sent = headers_for(url, &target, headers, extra_headers);
+        let response = transport.get_with_headers(&target, &sent)?;
+        match redirect_of(&target, &response)? {
+            Some(next) => target = next,
+            None => return Ok(response),
+        }
+    }
+
+    log::warn!("Too many redirects from {}", url);
+    Err(ClientError::Http)
+}
+
+/// Headers of a request to `target`, redirected from `url`: `extra_headers`, and `headers` if
+/// `target` has the origin of `url`.
+pub(crate) fn headers_for(
+    url: &str,
+    target: &str,
+    headers: &[(String, String)],
+    extra_headers: &[(String, String)],
+) -> Vec<(String, String)> {
+    let mut sent: Vec<(String, String)> = extra_headers.to_vec();
+    if origin(target) == origin(url) {
+        sent.extend(headers.iter().cloned());
+    }
+    sent
+}
+
+/// Where the `response` to a request to `target` redirects to, `None` if it's not a redirect.
+/// Fails on a redirect that can't be followed, e.g. to another scheme.
+pub(crate) fn redirect_of(
+    target: &str,
+    response: &HttpResponse,
+) -> Result<Option<String>, ClientError> {
+    if !matches!(response.code, 301 | 302 | 303 | 307 | 308) {
+        return Ok(None);
+    }
+    let next = response
+        .header("location")
+        .and_then(|location| redirect_target(target, location))
+        .ok_or_else(|| {
+            log::warn!("Invalid redirect from {}", target);
+            ClientError::Http
+        })?;
+    log::info!("Redirected to {}", next);
+    Ok(Some(next))
+}
+
+/// URL to follow a redirect from `url` to `location`, if it has the same scheme.
+/// `location` is either an absolute URL, or a path absolute to the origin of `url`.
+fn redirect_target(url: &str, location: &str) -> Option<String> {
+    let url_scheme = scheme(url)?;
+    if location.starts_with("//") {
+        return Some(format!("{url_scheme}:{location}"));
+    }
+    if location.starts_with('/') {
+        return Some(format!("{}{location}", origin(url)?));
+    }
+    (scheme(location)? == url_scheme).then(|| location.to_string())
+}
+
+fn scheme(url: &str) -> Option<&str> {
+    url.split_once("://").map(|(scheme, _)| scheme)
+}
+
+/// `scheme://host[:port]` of `url`.
+fn origin(url: &str) -> Option<&str> {
+    let (scheme, rest) = url.split_once("://")?;
+    let host_len = rest.find(['/', '?', '#']).unwrap_or(rest.len());
+    Some(&url[..scheme.len() + 3 + host_len])
+}
+
+/// Unix time (in seconds) of an HTTP date, in the preferred format
+/// (e.g. `Sun, 06 Nov 1994 08:49:37 GMT`). Out of range fields (including years past 9999, the
+/// header being up to the server) make the date invalid.
+fn parse_http_date(date: &str) -> Option<u64> {
+    let mut parts = date.split_whitespace().skip(1);
+    let day: u64 = parts.next()?.parse().ok()?;
+    let month: u64 = match parts.next()? {
+        "Jan" => 1,
+        "Feb" => 2,
+        "Mar" => 3,
+        "Apr" => 4,
+        "May" => 5,
+        "Jun" => 6,
+        "Jul" => 7,
+        "Aug" => 8,
+        "Sep" => 9,
+        "Oct" => 10,
+        "Nov" => 11,
+        "Dec" => 12,
+        _ => return None,
+    };
+    let year: u64 = parts.next()?.parse().ok()?;
+    let mut time = parts
+        .next()?
+        .split(':')
+        .map(|part| part.parse::<u64>().ok());
+    let (hours, minutes, seconds) = (time.next()??, time.next()??, time.next()??);
+    if parts.next()? != "GMT" || time.next().is_some() {
+        return None;
+    }
+    // a leap second is `60`
+    if !(1970..=9999).contains(&year)
+        || !(1..=31).contains(&day)
+        || hours >= 24
+        || minutes >= 60
+        || seconds > 60
+    {
+        return None;
+    }
+
+    // days since the epoch of the civil date, with years starting in March
+    let (year, month) = if month <= 2 {
+        (year.checked_sub(1)?, month + 9)
+    } else {
+        (year, month - 3)
+    };
+    let era = year / 400;
+    let year_of_era = year - era * 400;
+    let day_of_year = ((153 * month + 2) / 5).checked_add(day)?.checked_sub(1)?;
+    let day_of_era = year_of_era
+        .checked_mul(365)?
+        .checked_add(year_of_era / 4)?
+        .checked_sub(year_of_era / 100)?
+        .checked_add(day_of_year)?;
+    let days = era
+        .checked_mul(146_097)?
+        .checked_add(day_of_era)?
+        .checked_sub(719_468)?;
+
+    days.checked_mul(86_400)?
+        .checked_add(hours * 3_600)?
+        .checked_add(minutes * 60)?
+        .checked_add(seconds)
+}
+
+#[test]
+fn test_parse_http_date() {
+    assert_eq!(
+        parse_http_date("Sun, 06 Nov 1994 08:49:37 GMT"),
+        Some(784_111_777)
+    );
+    assert_eq!(parse_http_date("Thu, 01 Jan 1970 00:00:00 GMT"), Some(0));
+    assert_eq!(
+        parse_http_date("Tue, 29 Feb 2000 12:00:00 GMT"),
+        Some(951_825_600)
+    );
+    assert_eq!(parse_http_date("Sunday, 06-Nov-94 08:49:37 GMT"), None);
+
+    // out of range fields
+    assert_eq!(parse_http_date("Sun, 00 Nov 1994 08:49:37 GMT"), None);
+    assert_eq!(parse_http_date("Sun, 32 Nov 1994 08:49:37 GMT"), None);
+    assert_eq!(parse_http_date("Sun, 06 Nov 1994 24:00:00 GMT"), None);
+    assert_eq!(parse_http_date("Sun, 06 Nov 1994 08:60:00 GMT"), None);
+    assert_eq!(parse_http_date("Sun, 06 Nov 1994 08:49:61 GMT"), None);
+    assert_eq!(parse_http_date("Sun, 06 Nov 1994 08:49:37:00 GMT"), None);
+    assert_eq!(
+        parse_http_date("Sun, 06 Nov 1994 99999999999999999:00:00 GMT"),
+        None
+    );
+    assert_eq!(
+        parse_http_date("Sun, 99999999999999999 Nov 1994 08:49:37 GMT"),
+        None
+    );
+    // would overflow the days since the epoch
+    assert_eq!(
+        parse_http_date("Sun, 06 Nov 18446744073709551615 08:49:37 GMT"),
+        None
+    );
+}
+
+#[test]
+fn test_redirect_target() {
+    let url = "https://relay.example.com:8443/public/latest";
+    assert_eq!(
+        redirect_target(url, "/v2/public/latest").as_deref(),
+        Some("https://relay.example.com:8443/v2/public/latest")
+    );
+    assert_eq!(
+        redirect_target(url, "https://new.example.com/public/latest").as_deref(),
+        Some("https://new.example.com/public/latest")
+    );
+    assert_eq!(
+        redirect_target(url, "//new.example.com/public/latest").as_deref(),
+        Some("https://new.example.com/public/latest")
+    );
+    assert_eq!(
+        redirect_target(url, "http://new.example.com/public/latest"),
+        None
+    );
+    assert_eq!(redirect_target(url, "latest"), None);
+}
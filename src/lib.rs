@@ -3,31 +3,81 @@
 #[cfg(all(test, feature = "std"))]
 mod tests;
 
+mod api;
+#[cfg(feature = "async")]
+mod async_client;
+mod backfill;
+mod bls;
+mod builder;
+mod cache;
 mod data_structures;
+mod derive;
+mod endpoint;
+mod headers;
+mod health;
+mod http;
+mod lock;
+mod migration;
+mod misbehaviour;
+mod mix;
+mod multi;
+#[cfg(feature = "native-http")]
+mod native;
+mod networks;
+mod requests;
+mod retry;
+mod rng;
+mod stats;
+pub mod tle;
+pub mod tlock;
+mod transport;
 mod util;
 
+pub use crate::api::ApiVersion;
+#[cfg(feature = "async")]
+pub use crate::async_client::*;
+pub use crate::backfill::*;
+pub use crate::bls::{round_message, verify_signature};
+pub use crate::builder::*;
+pub use crate::cache::*;
 pub use crate::data_structures::*;
-
-use sp_runtime::offchain::{
-    http::{Error, Request},
-    Duration,
-};
-
-use drand_verify::g1_from_variable;
+pub use crate::derive::*;
+pub use crate::endpoint::*;
+pub use crate::headers::*;
+pub use crate::health::*;
+pub use crate::http::*;
+pub use crate::lock::*;
+pub use crate::migration::*;
+pub use crate::misbehaviour::*;
+pub use crate::mix::*;
+pub use crate::multi::*;
+#[cfg(feature = "native-http")]
+pub use crate::native::*;
+pub use crate::networks::*;
+pub use crate::requests::*;
+pub use crate::retry::*;
+pub use crate::rng::*;
+pub use crate::stats::*;
+pub use crate::transport::*;
+
+use drand_verify::{derive_randomness, g1_from_variable};
+
+use crate::api::ApiPath;
 
 use codec::{Decode, Encode};
 use frame_support::{dispatch::MaxEncodedLen, Deserialize, Serialize};
-use scale_info::prelude::format;
+use scale_info::prelude::{format, string::String};
+use serde::de::DeserializeOwned;
 use sp_runtime::{traits::ConstU32, BoundedVec, RuntimeDebug};
 use sp_std::str;
 use sp_std::vec::Vec;
 
 /// drand client errors
-#[derive(RuntimeDebug)]
+#[derive(PartialEq, Eq, RuntimeDebug)]
 pub enum ClientError {
-    /// TODO
+    /// The HTTP request could not be completed or returned an unexpected status code
     Http,
-    /// TODO
+    /// The response body is not the expected JSON
     Json,
     Unknown,
     /// The round was was unable to be verified
@@ -38,6 +88,22 @@ pub enum ClientError {
     Misc,
     /// Signature verification failed
     InvalidSignature,
+    /// The chain info doesn't hash to the expected chain hash
+    ChainHashMismatch,
+    /// Another chain is already pinned as `chain_info`
+    ChainAlreadyPinned,
+    /// Neither the endpoint nor any relay is in sync with the chain
+    NoHealthyEndpoint,
+    /// The endpoint is backed off after failed requests, and isn't requested until later
+    RetryLater,
+    /// The response body is larger than the transport accepts
+    ResponseTooLarge,
+    /// The response comes from a cache, and is too old even when requested without caches
+    StaleResponse,
+    /// The endpoint URL is malformed, or isn't an `http(s)` base URL
+    InvalidEndpoint,
+    /// The client configuration is inconsistent, e.g. a `lock` without a `cache`
+    InvalidConfiguration,
 }
 
 /// Client is a wrapper around the offchain http client.
@@ -56,84 +122,181 @@ pub enum ClientError {
     Deserialize,
 )]
 pub struct Client {
-    pub endpoint: BoundedVec<u8, ConstU32<100>>,
+    pub endpoint: Endpoint,
     pub chain_info: Option<Info>,
+    /// Cache verified rounds in offchain local storage. Requires `chain_info`.
+    pub cache: Option<CacheConfig>,
+    /// Lock fetches across concurrent offchain workers. Requires `cache`.
+    pub lock: Option<LockConfig>,
+    /// Relays tried after `endpoint` by `select_endpoint`.
+    #[serde(default)]
+    pub relays: BoundedVec<Endpoint, ConstU32<MAX_RELAYS>>,
+    /// Version of the HTTP API served by the endpoints.
+    #[serde(default)]
+    pub api_version: ApiVersion,
+    /// Record statistics of the requests to `endpoint`, for `preferred_endpoint`.
+    #[serde(default)]
+    pub stats: Option<StatsConfig>,
+    /// Retry failed requests, and back off `endpoint` across runs when they keep failing.
+    #[serde(default)]
+    pub retry: Option<RetryConfig>,
+    /// Headers sent with the requests, per endpoint.
+    #[serde(default)]
+    pub headers: ClientHeaders,
+    /// Deadlines and body size limit of the requests made without a given transport.
+    #[serde(default)]
+    pub transport: OffchainTransport,
+    /// Which rounds are verified against `chain_info`.
+    #[serde(default)]
+    pub verification: Verification,
 }
 
-impl Default for Client {
-    /// League of Entropy base urls and chain info (from https://drand.cloudflare.com/info)
-    #[cfg(not(test))]
-    fn default() -> Self {
-        Client {
-            endpoint: "https://drand.cloudflare.com"
-                .as_bytes()
-                .to_owned()
-                .try_into()
-                .unwrap(),
-            chain_info: Some(Info::default()),
-        }
-    }
+/// Which rounds a `Client` verifies before returning them.
+#[derive(
+    Encode,
+    Decode,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    RuntimeDebug,
+    scale_info::TypeInfo,
+    MaxEncodedLen,
+    Serialize,
+    Deserialize,
+)]
+pub enum Verification {
+    /// Rounds requested by number, and `latest` rounds when they are cached.
+    #[default]
+    Standard,
+    /// Every round, including `latest` rounds that aren't cached. Requires `chain_info`.
+    Strict,
+}
 
-    #[cfg(test)]
+impl Default for Client {
+    /// League of Entropy mainnet chain, from `MAINNET_ENDPOINT`.
+    /// `ClientBuilder::mainnet` also configures the other League of Entropy relays.
     fn default() -> Self {
-        Client {
-            endpoint: "http://localhost".as_bytes().to_owned().try_into().unwrap(),
-            chain_info: Some(Info::default()),
-        }
+        Client::with_endpoint(
+            Endpoint::parse(MAINNET_ENDPOINT).expect("valid endpoint"),
+            Info::default(),
+        )
     }
 }
 
 impl Client {
-    // Creates a new drand client with the provided URL and chain_info.
-    pub fn new(url: &str, chain_info: Info) -> Self {
+    /// Creates a new drand client with the provided URL and chain_info.
+    /// Fails with `InvalidEndpoint` if `url` isn't a valid `Endpoint`.
+    pub fn new(url: &str, chain_info: Info) -> Result<Self, ClientError> {
+        Ok(Client::with_endpoint(Endpoint::parse(url)?, chain_info))
+    }
+
+    /// Creates a new drand client of `endpoint` and chain_info.
+    pub fn with_endpoint(endpoint: Endpoint, chain_info: Info) -> Self {
         Client {
-            endpoint: url.as_bytes().to_owned().to_vec().try_into().unwrap(),
+            endpoint,
             chain_info: Some(chain_info),
+            cache: None,
+            lock: None,
+            relays: Default::default(),
+            api_version: ApiVersion::V1,
+            stats: None,
+            retry: None,
+            headers: Default::default(),
+            transport: Default::default(),
+            verification: Default::default(),
         }
     }
 
-    /// HTTP GET on the chain's `/chains` endpoint.
-    pub fn chains(&self) -> Result<Chains, Error> {
-        let mut url_str = self.endpoint.clone().to_vec();
-        url_str.extend("/chains".as_bytes().to_vec());
-        let body = self.make_request(url_str).unwrap();
-
-        // Create a str slice from the body.
-        let body_str = sp_std::str::from_utf8(&body).map_err(|_| {
-            log::warn!("No UTF8 body");
-            Error::Unknown
-        })?;
+    /// Creates a client of the chain `chain_info`, served under its chain hash by an endpoint
+    /// hosting several chains, i.e. at `{base_url}/{chain hash}`.
+    pub fn for_chain(base_url: &str, chain_info: Info) -> Result<Self, ClientError> {
+        let endpoint = Endpoint::parse(base_url)?.join(&util::vec_u8_to_hex(&chain_info.hash))?;
+        Ok(Client::with_endpoint(endpoint, chain_info))
+    }
 
-        log::info!("Response: {}", body_str);
+    /// HTTP GET on the chain's `/chains` endpoint.
+    pub fn chains(&self) -> Result<Chains, ClientError> {
+        self.chains_with(&self.transport)
+    }
 
-        let chains_raw: ChainsRaw = serde_json::from_str(body_str).map_err(|_| {
-            log::warn!("Failed to deserialize");
-            Error::Unknown
-        })?;
+    /// Same as `chains`, fetching through the given transport.
+    pub fn chains_with<T: Transport>(&self, transport: &T) -> Result<Chains, ClientError> {
+        let chains_raw: ChainsRaw = self.get_json(transport, &self.path(ApiPath::Chains))?;
 
         Ok(Chains::from(chains_raw))
     }
 
     /// HTTP GET on the chain's `/info` endpoint.
-    pub fn info(&self) -> Result<Info, Error> {
-        let mut url_str = self.endpoint.clone().to_vec();
-        url_str.extend("/info".as_bytes().to_vec());
-        let body = self.make_request(url_str).unwrap();
+    pub fn info(&self) -> Result<Info, ClientError> {
+        self.info_with(&self.transport)
+    }
 
-        // Create a str slice from the body.
-        let body_str = sp_std::str::from_utf8(&body).map_err(|_| {
-            log::warn!("No UTF8 body");
-            Error::Unknown
-        })?;
+    /// Same as `info`, fetching through the given transport.
+    pub fn info_with<T: Transport>(&self, transport: &T) -> Result<Info, ClientError> {
+        self.get_info(transport, &self.path(ApiPath::Info))
+    }
 
-        log::info!("Response: {}", body_str);
+    /// Lists the chains served by the endpoint with `/chains`, and fetches the info of each one,
+    /// keeping the chains whose info hashes to their chain hash. One of them can then be pinned
+    /// with `pin_chain`, e.g. by governance.
+    pub fn bootstrap(&self) -> Result<Vec<Info>, ClientError> {
+        self.bootstrap_with(&self.transport)
+    }
 
-        let info_raw: InfoRaw = serde_json::from_str(body_str).map_err(|_| {
-            log::warn!("Failed to deserialize");
-            Error::Unknown
-        })?;
+    /// Same as `bootstrap`, fetching through the given transport.
+    pub fn bootstrap_with<T: Transport>(&self, transport: &T) -> Result<Vec<Info>, ClientError> {
+        let chains = self.chains_with(transport)?;
+
+        Ok(chains
+            .hashes
+            .iter()
+            .filter_map(|hash| match self.chain_info_of_with(transport, hash) {
+                Ok(info) => Some(info),
+                Err(e) => {
+                    log::warn!("Skipping chain {}: {:?}", util::vec_u8_to_hex(hash), e);
+                    None
+                }
+            })
+            .collect())
+    }
 
-        Ok(Info::from(info_raw))
+    /// HTTP GET on `/{hash}/info`, checking that the info hashes to `hash`.
+    pub fn chain_info_of(&self, hash: &[u8]) -> Result<Info, ClientError> {
+        self.chain_info_of_with(&self.transport, hash)
+    }
+
+    /// Same as `chain_info_of`, fetching through the given transport.
+    pub fn chain_info_of_with<T: Transport>(
+        &self,
+        transport: &T,
+        hash: &[u8],
+    ) -> Result<Info, ClientError> {
+        let info = self.get_info(transport, &self.path(ApiPath::ChainInfo(hash)))?;
+
+        if info.hash.as_slice() != hash || !info.has_valid_hash() {
+            return Err(ClientError::ChainHashMismatch);
+        }
+        Ok(info)
+    }
+
+    /// Pins `info` as the chain of the client, if it has none yet: it fails if another chain is
+    /// already pinned (use `set_chain_info` to replace it deliberately), or if the info doesn't
+    /// hash to its chain hash.
+    /// The constructors and `Client::default` already set a chain, so this is only trust on
+    /// first use for clients built without one, with `ClientBuilder`.
+    pub fn pin_chain(&mut self, info: Info) -> Result<(), ClientError> {
+        if !info.has_valid_hash() {
+            return Err(ClientError::ChainHashMismatch);
+        }
+        match &self.chain_info {
+            Some(pinned) if pinned.hash != info.hash => Err(ClientError::ChainAlreadyPinned),
+            _ => {
+                self.chain_info = Some(info);
+                Ok(())
+            }
+        }
     }
 
     /// Associates the client to a specific chain. Required to verify randomness.
@@ -149,103 +312,267 @@ impl Client {
         }
     }
 
+    /// Sets the deadlines and body size limit of the requests made without a given transport.
+    pub fn set_transport(&mut self, transport: OffchainTransport) {
+        self.transport = transport;
+    }
+
+    /// Sets which rounds are verified. `Verification::Strict` requires `chain_info`.
+    pub fn set_verification(&mut self, verification: Verification) {
+        self.verification = verification;
+    }
+
+    /// Enables (or disables, with `None`) the offchain cache of verified rounds.
+    pub fn set_cache(&mut self, cache: Option<CacheConfig>) {
+        self.cache = cache;
+    }
+
+    /// Enables (or disables, with `None`) locking fetches across concurrent offchain workers,
+    /// so only one of them fetches a given round. Only used together with the cache.
+    pub fn set_lock(&mut self, lock: Option<LockConfig>) {
+        self.lock = lock;
+    }
+
+    /// The offchain cache of the configured chain, if caching is enabled.
+    pub fn round_cache(&self) -> Option<RoundCache<'_>> {
+        let chain_info = self.chain_info.as_ref()?;
+        let config = self.cache.as_ref()?;
+        Some(RoundCache::new(&chain_info.hash, config))
+    }
+
     /// Queries and verifies a round of randomness.
-    pub fn round(&self, round: u64) -> Result<Round, Error> {
-        let mut url_str = self.endpoint.clone().to_vec();
-        url_str.extend(format!("/public/{round}").as_bytes().to_vec());
-        let body = self.make_request(url_str).unwrap();
+    /// With caching enabled, the round is only fetched if it isn't cached yet.
+    pub fn round(&self, round: u64) -> Result<Round, ClientError> {
+        self.round_with(&self.transport, round)
+    }
 
-        // Create a str slice from the body.
-        let body_str = sp_std::str::from_utf8(&body).map_err(|_| {
-            log::warn!("No UTF8 body");
-            Error::Unknown
-        })?;
+    /// Same as `round`, fetching through the given transport.
+    pub fn round_with<T: Transport>(
+        &self,
+        transport: &T,
+        round: u64,
+    ) -> Result<Round, ClientError> {
+        let chain_info = self.chain_info()?;
+        let cache = self.round_cache();
+        let cached = || cache.as_ref().and_then(|cache| cache.get(round));
+        if let Some(cached) = cached() {
+            return Ok(cached);
+        }
 
-        log::info!("Response: {}", body_str);
+        self.with_fetch_lock(&format!("round::{round}"), || {
+            // another worker may have fetched the round while we were waiting for the lock
+            if let Some(cached) = cached() {
+                return Ok(cached);
+            }
 
-        let round = Round::from(RoundRaw::from(serde_json::from_str(body_str).map_err(
-            |_| {
-                log::warn!("Failed to deserialize");
-                Error::Unknown
-            },
-        )?));
+            let fetched = self.get_round(transport, &self.path(ApiPath::Round(round)))?;
 
-        // TODO fix unwrap
-        Client::verify_randomness(&round, &self.chain_info.clone().unwrap().public_key)
-            .map_err(|_| Error::Unknown)?;
+            Client::verify_round(&fetched, &chain_info)?;
 
-        Ok(round)
+            if let Some(cache) = &cache {
+                cache.insert(&fetched);
+            }
+
+            Ok(fetched)
+        })
     }
 
     /// This fetches the latest round from the drand server.
-    pub fn latest(&self) -> Result<Round, Error> {
-        let mut url_str = self.endpoint.clone().to_vec();
-        url_str.extend("/public/latest".as_bytes().to_vec());
-        let body = self.make_request(url_str).unwrap();
+    /// With caching enabled, the round is verified before being cached, and the cached round
+    /// is returned until the next round is expected to be emitted. With `Verification::Strict`,
+    /// the round is verified even without caching.
+    pub fn latest(&self) -> Result<Round, ClientError> {
+        self.latest_with(&self.transport)
+    }
+
+    /// Same as `latest`, fetching through the given transport.
+    pub fn latest_with<T: Transport>(&self, transport: &T) -> Result<Round, ClientError> {
+        let cache = self.round_cache();
+        // the cached latest round, as long as the next one isn't due
+        let fresh_latest = || {
+            let chain_info = self.chain_info.as_ref()?;
+            let latest = cache.as_ref()?.latest()?;
+            let now = sp_io::offchain::timestamp().unix_millis() / 1_000;
+            (now < chain_info.round_time(latest.round + 1)).then_some(latest)
+        };
+        if let Some(latest) = fresh_latest() {
+            return Ok(latest);
+        }
+
+        self.with_fetch_lock("latest", || {
+            if let Some(latest) = fresh_latest() {
+                return Ok(latest);
+            }
+
+            let round = self.fetch_latest(transport)?;
+
+            if cache.is_some() || self.verification == Verification::Strict {
+                Client::verify_round(&round, &self.chain_info()?)?;
+            }
+            if let Some(cache) = &cache {
+                cache.set_latest(&round);
+            }
+
+            Ok(round)
+        })
+    }
+
+    /// Runs `f` holding the cross-worker lock `name`, if both `cache` and `lock` are configured.
+    fn with_fetch_lock<R>(&self, name: &str, f: impl FnOnce() -> R) -> R {
+        match (&self.lock, &self.cache, &self.chain_info) {
+            (Some(lock), Some(_), Some(chain_info)) => {
+                lock.with_lock(&lock_key(&chain_info.hash, name.as_bytes()), f)
+            }
+            _ => f(),
+        }
+    }
+
+    /// Sets the version of the HTTP API served by the endpoints.
+    pub fn set_api_version(&mut self, api_version: ApiVersion) {
+        self.api_version = api_version;
+    }
 
+    /// Path of an API endpoint, for the configured API version and beacon.
+    pub(crate) fn path(&self, path: ApiPath) -> String {
+        let beacon_id = self
+            .chain_info
+            .as_ref()
+            .and_then(|info| str::from_utf8(&info.beacon_id).ok())
+            .filter(|beacon_id| !beacon_id.is_empty())
+            .unwrap_or("default");
+        self.api_version.path(beacon_id, path)
+    }
+
+    /// Fetches the info at `path`.
+    fn get_info<T: Transport>(&self, transport: &T, path: &str) -> Result<Info, ClientError> {
+        self.parse_info(&self.make_request_with(transport, self.url(path))?)
+    }
+
+    /// Fetches the round at `path`.
+    fn get_round<T: Transport>(&self, transport: &T, path: &str) -> Result<Round, ClientError> {
+        self.parse_round(&self.make_request_with(transport, self.url(path))?)
+    }
+
+    /// Deserializes chain info, in the shape of the configured API version.
+    pub(crate) fn parse_info(&self, body: &[u8]) -> Result<Info, ClientError> {
+        Ok(match self.api_version {
+            ApiVersion::V1 => Info::from(Client::parse_json::<InfoRaw>(body)?),
+            ApiVersion::V2 => Info::from(Client::parse_json::<InfoV2Raw>(body)?),
+        })
+    }
+
+    /// Deserializes a round, in the shape of the configured API version.
+    pub(crate) fn parse_round(&self, body: &[u8]) -> Result<Round, ClientError> {
+        self.api_version.parse_round(body)
+    }
+
+    /// Fetches `path` relative to the endpoint and deserializes the JSON body.
+    fn get_json<T: Transport, D: DeserializeOwned>(
+        &self,
+        transport: &T,
+        path: &str,
+    ) -> Result<D, ClientError> {
+        let body = self.make_request_with(transport, self.url(path))?;
+
+        Client::parse_json(&body)
+    }
+
+    /// URL of `path` relative to the endpoint. The v2 API addresses chains in its paths, from the
+    /// root of a relay, so an endpoint under the chain hash (e.g. from `ClientBuilder::quicknet`)
+    /// is used without it.
+    pub(crate) fn url(&self, path: &str) -> Vec<u8> {
+        let chain_path = match (&self.api_version, &self.chain_info) {
+            (ApiVersion::V2, Some(info)) => format!("/{}", util::vec_u8_to_hex(&info.hash)),
+            _ => return self.endpoint.url(path).into_bytes(),
+        };
+        let endpoint = self.endpoint.as_str();
+        let base = endpoint.strip_suffix(&chain_path).unwrap_or(endpoint);
+        format!("{base}{path}").into_bytes()
+    }
+
+    /// Deserializes a JSON response body.
+    pub(crate) fn parse_json<D: DeserializeOwned>(body: &[u8]) -> Result<D, ClientError> {
         // Create a str slice from the body.
-        let body_str = sp_std::str::from_utf8(&body).map_err(|_| {
+        let body_str = sp_std::str::from_utf8(body).map_err(|_| {
             log::warn!("No UTF8 body");
-            Error::Unknown
+            ClientError::Json
         })?;
 
         log::info!("Response: {}", body_str);
 
-        let round_raw: RoundRaw = serde_json::from_str(body_str).map_err(|_| {
+        serde_json::from_str(body_str).map_err(|_| {
             log::warn!("Failed to deserialize");
-            Error::Unknown
-        })?;
+            ClientError::Json
+        })
+    }
 
-        Ok(Round::from(round_raw))
-    }
-
-    pub fn make_request(&self, url: Vec<u8>) -> Result<Vec<u8>, Error> {
-        // We want to keep the offchain worker execution time reasonable, so we set a hard-coded
-        // deadline to 2s to complete the external call.
-        // You can also wait idefinitely for the response, however you may still get a timeout
-        // coming from the host machine.
-        let send_deadline = sp_io::offchain::timestamp().add(Duration::from_millis(2_000));
-        // Initiate an external HTTP GET request.
-        // This is using high-level wrappers from `sp_runtime`, for the low-level calls that
-        // you can find in `sp_io`. The API is trying to be similar to `reqwest`, but
-        // since we are running in a custom WASM execution environment we can't simply
-        // import the library here.
-        let url_str = unsafe { str::from_utf8_unchecked(&url) };
-        let request = Request::get(url_str);
-        // We set the deadline for sending of the request, note that awaiting response can
-        // have a separate deadline. Next we send the request, before that it's also possible
-        // to alter request headers or stream body content in case of non-GET requests.
-        let pending = request
-            .deadline(send_deadline)
-            .send()
-            .map_err(|_| Error::IoError)?;
-
-        // The request is already being processed by the host, we are free to do anything
-        // else in the worker (we can send multiple concurrent requests too).
-        // At some point however we probably want to check the response though,
-        // so we can block current thread and wait for it to finish.
-        // Note that since the request is being driven by the host, we don't have to wait
-        // for the request to have it complete, we will just not read the response.
-
-        // TODO: Right now the response deadline is same as send deadline, but maybe let's make them differnet later.
-        let response_deadline = sp_io::offchain::timestamp().add(Duration::from_millis(2_000));
-        let response = pending
-            .try_wait(response_deadline)
-            .map_err(|_| Error::DeadlineReached)??;
+    /// Returns the body of a `200` response.
+    pub(crate) fn check_response(response: HttpResponse) -> Result<Vec<u8>, ClientError> {
         // Let's check the status code before we proceed to reading the response.
         if response.code != 200 {
             log::warn!("Unexpected status code: {}", response.code);
-            return Err(Error::Unknown);
+            return Err(ClientError::Http);
+        }
+
+        Ok(response.body)
+    }
+
+    /// HTTP GET on `url` through the offchain worker HTTP API.
+    pub fn make_request(&self, url: Vec<u8>) -> Result<Vec<u8>, ClientError> {
+        self.make_request_with(&self.transport, url)
+    }
+
+    /// HTTP GET on `url` through the given transport, returning the body of a `200` response.
+    /// Redirects to the same scheme are followed, and the configured headers of `endpoint` are
+    /// sent along. With `stats` enabled, the outcome is recorded against `endpoint`. With
+    /// `retry` enabled, failed requests are retried, and `endpoint` is backed off when they keep
+    /// failing.
+    pub fn make_request_with<T: Transport>(
+        &self,
+        transport: &T,
+        url: Vec<u8>,
+    ) -> Result<Vec<u8>, ClientError> {
+        let url_str = str::from_utf8(&url).map_err(|_| {
+            log::warn!("Invalid UTF8 url");
+            ClientError::Http
+        })?;
+
+        self.fetch_with(transport, url_str, &[])
+            .map(|response| response.body)
+    }
+
+    /// Static function to verify a round of the chain `info`, whatever its scheme.
+    /// The signature must be valid for the round (and the previous signature, for chained
+    /// schemes), and the randomness must be derived from the signature.
+    pub fn verify_round(
+        round: &Round,
+        info: &Info,
+    ) -> Result<BoundedVec<u8, ConstU32<32>>, ClientError> {
+        let randomness = if info.scheme.is_chained() {
+            // the public keys of the chained scheme are on G1
+            let public_key: BoundedVec<u8, ConstU32<48>> = info
+                .public_key
+                .to_vec()
+                .try_into()
+                .map_err(|_| ClientError::RandomnessVerificationError)?;
+            Client::verify_randomness(round, &public_key)?
+        } else {
+            let msg = bls::round_message(info.scheme, round.round, &round.previous_signature);
+            match bls::verify_signature(info.scheme, &info.public_key, &msg, &round.signature) {
+                Some(true) => round.randomness.clone(),
+                Some(false) => return Err(ClientError::InvalidSignature),
+                None => return Err(ClientError::RandomnessVerificationError),
+            }
+        };
+
+        if randomness.as_slice() != derive_randomness(&round.signature).as_slice() {
+            return Err(ClientError::RandomnessVerificationError);
         }
 
-        // Next we want to fully read the response body and collect it to a vector of bytes.
-        // Note that the return object allows you to read the body in chunks as well
-        // with a way to control the deadline.
-        let body = response.body().collect::<Vec<u8>>();
-        Ok(body)
+        Ok(randomness)
     }
 
-    /// Static function to verify a round against a public key
+    /// Static function to verify a round against a public key of the `pedersen-bls-chained` scheme
     pub fn verify_randomness(
         round: &Round,
         pub_key_vec: &BoundedVec<u8, ConstU32<48>>,
@@ -0,0 +1,117 @@
+//! Cross-worker locking around fetches.
+//! Offchain workers of consecutive blocks can run concurrently on the same node. When the
+//! `Client` has both `cache` and `lock` configured, only the worker holding the lock of a round
+//! fetches it, while the others wait for the lock and then read the cached round.
+
+use codec::{Decode, Encode, MaxEncodedLen};
+use serde::{Deserialize, Serialize};
+use sp_runtime::{
+    offchain::{
+        storage_lock::{BlockAndTime, StorageLock, Time},
+        Duration,
+    },
+    traits::BlockNumberProvider,
+    RuntimeDebug,
+};
+use sp_std::vec::Vec;
+
+/// Prefix of all the offchain storage keys used as locks.
+pub const LOCK_KEY_PREFIX: &[u8] = b"drand-substrate-client::lock::";
+
+/// How long a lock is held at most, e.g. if the worker holding it is killed.
+/// It must outlast a fetch: `ClientBuilder::build` checks it covers every attempt (send and
+/// read deadlines of the transport) and the waits between them.
+#[derive(
+    Encode,
+    Decode,
+    Clone,
+    PartialEq,
+    Eq,
+    RuntimeDebug,
+    scale_info::TypeInfo,
+    MaxEncodedLen,
+    Serialize,
+    Deserialize,
+)]
+pub enum LockConfig {
+    /// The lock expires `expiration_ms` milliseconds after being taken.
+    Time { expiration_ms: u64 },
+    /// The lock expires once both `expiration_blocks` blocks and `expiration_ms` milliseconds
+    /// have passed since it was taken. The current block is read from `frame_system`.
+    BlockAndTime {
+        expiration_blocks: u32,
+        expiration_ms: u64,
+    },
+}
+
+impl Default for LockConfig {
+    fn default() -> Self {
+        LockConfig::Time {
+            expiration_ms: 5_000,
+        }
+    }
+}
+
+impl LockConfig {
+    /// Milliseconds the lock is held at least, unless released.
+    pub fn expiration_ms(&self) -> u64 {
+        match *self {
+            LockConfig::Time { expiration_ms } => expiration_ms,
+            LockConfig::BlockAndTime { expiration_ms, .. } => expiration_ms,
+        }
+    }
+
+    /// Runs `f` while holding the offchain lock `key`, waiting for it if another worker holds it.
+    pub fn with_lock<R>(&self, key: &[u8], f: impl FnOnce() -> R) -> R {
+        match *self {
+            LockConfig::Time { expiration_ms } => {
+                let mut lock =
+                    StorageLock::<Time>::with_deadline(key, Duration::from_millis(expiration_ms));
+                let _guard = lock.lock();
+                f()
+            }
+            LockConfig::BlockAndTime {
+                expiration_blocks,
+                expiration_ms,
+            } => {
+                let mut lock =
+                    StorageLock::<BlockAndTime<SystemBlockNumber>>::with_block_and_time_deadline(
+                        key,
+                        expiration_blocks,
+                        Duration::from_millis(expiration_ms),
+                    );
+                let _guard = lock.lock();
+                f()
+            }
+        }
+    }
+}
+
+/// Offchain storage key of the lock `name` of a chain.
+pub fn lock_key(chain_hash: &[u8], name: &[u8]) -> Vec<u8> {
+    let mut key = LOCK_KEY_PREFIX.to_vec();
+    key.extend(chain_hash);
+    key.extend(b"::");
+    key.extend(name);
+    key
+}
+
+/// Current block number, read from `frame_system`'s `Number` storage item so the client
+/// doesn't depend on the runtime's `Config`. Reads `0` if it isn't set.
+pub struct SystemBlockNumber;
+
+impl BlockNumberProvider for SystemBlockNumber {
+    type BlockNumber = u64;
+
+    fn current_block_number() -> u64 {
+        let mut key = sp_io::hashing::twox_128(b"System").to_vec();
+        key.extend(sp_io::hashing::twox_128(b"Number"));
+
+        // runtimes use either `u32` or `u64` block numbers
+        match sp_io::storage::get(&key) {
+            Some(raw) if raw.len() == 4 => u32::decode(&mut &raw[..]).unwrap_or_default().into(),
+            Some(raw) => u64::decode(&mut &raw[..]).unwrap_or_default(),
+            None => 0,
+        }
+    }
+}
@@ -0,0 +1,78 @@
+//! SCALE layouts of the `Client` configuration stored by earlier versions, and their conversion
+//! to the current ones, for the storage migration of a pallet storing a `Client`.
+//! Version 0 stored only the endpoint (as raw bytes) and the info of a `pedersen-bls-chained`
+//! chain (48 bytes public key, no scheme nor beacon id). The current layout adds the settings of
+//! the `Client` (relays, cache, retries, headers, ...) and the scheme and beacon id of `Info`, so
+//! a value stored by version 0 doesn't decode as a `Client`: decode it as a `ClientV0`, or with
+//! `Client::decode_versioned`, and store it again.
+
+use codec::{Decode, DecodeAll, Encode, MaxEncodedLen};
+use sp_runtime::{traits::ConstU32, BoundedVec, RuntimeDebug};
+use sp_std::str;
+
+use crate::{Client, ClientError, Endpoint, Info, Scheme, DEFAULT_BEACON_ID};
+
+/// `Info` as stored by version 0.
+#[derive(
+    Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, scale_info::TypeInfo, MaxEncodedLen,
+)]
+pub struct InfoV0 {
+    pub public_key: BoundedVec<u8, ConstU32<48>>,
+    pub period: u64,
+    pub genesis_time: u64,
+    pub hash: BoundedVec<u8, ConstU32<32>>,
+    pub group_hash: BoundedVec<u8, ConstU32<32>>,
+}
+
+/// `Client` as stored by version 0.
+#[derive(
+    Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, scale_info::TypeInfo, MaxEncodedLen,
+)]
+pub struct ClientV0 {
+    pub endpoint: BoundedVec<u8, ConstU32<100>>,
+    pub chain_info: Option<InfoV0>,
+}
+
+impl From<InfoV0> for Info {
+    /// Version 0 only supported the `default` beacon of `pedersen-bls-chained` chains.
+    fn from(info: InfoV0) -> Self {
+        Info {
+            public_key: info.public_key.into_inner().try_into().unwrap_or_default(),
+            period: info.period,
+            genesis_time: info.genesis_time,
+            hash: info.hash,
+            group_hash: info.group_hash,
+            scheme: Scheme::PedersenBlsChained,
+            beacon_id: DEFAULT_BEACON_ID.to_vec().try_into().unwrap_or_default(),
+        }
+    }
+}
+
+impl TryFrom<ClientV0> for Client {
+    type Error = ClientError;
+
+    /// The endpoint must be a valid `Endpoint`, the other settings get their default.
+    fn try_from(client: ClientV0) -> Result<Self, Self::Error> {
+        let url = str::from_utf8(&client.endpoint).map_err(|_| ClientError::InvalidEndpoint)?;
+        Ok(Client {
+            endpoint: Endpoint::parse(url)?,
+            chain_info: client.chain_info.map(Info::from),
+            ..Client::default()
+        })
+    }
+}
+
+impl Client {
+    /// Decodes a `Client` stored with the current layout, or else with the layout of version 0.
+    /// Fails with `Misc` if `encoded` is neither.
+    pub fn decode_versioned(encoded: &[u8]) -> Result<Client, ClientError> {
+        if let Ok(client) = Client::decode_all(&mut &encoded[..]) {
+            return Ok(client);
+        }
+        let client = ClientV0::decode_all(&mut &encoded[..]).map_err(|_| {
+            log::warn!("Not an encoded Client");
+            ClientError::Misc
+        })?;
+        Client::try_from(client)
+    }
+}
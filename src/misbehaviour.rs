@@ -0,0 +1,226 @@
+//! Detection of relays serving rounds that don't verify, with reports a pallet can check and act
+//! upon (e.g. store them, or remove the relay by governance).
+//! drand signatures are unique: for a given round and chain, at most one signature verifies, so
+//! a payload that doesn't verify is wrong. But relays don't sign their HTTP responses: a
+//! `MisbehaviourReport` carries the payload as it was served, and only the reporter attests that
+//! the relay served it. Anyone can make up a report against any relay, and
+//! `MisbehaviourReport::check` only tells whether its payload fails the way it's reported. A
+//! relay should only be acted upon once reported by enough distinct reporters (e.g. validators
+//! running the offchain worker), see `reported_by_quorum`.
+
+use codec::{Decode, Encode, MaxEncodedLen};
+use drand_verify::derive_randomness;
+use sp_runtime::{traits::ConstU32, BoundedVec, RuntimeDebug};
+use sp_std::vec::Vec;
+
+use crate::{api::ApiPath, ApiVersion, Client, ClientError, Endpoint, Info, Round, Transport};
+
+/// Maximum size of the payload of a report. Larger responses can't be reported.
+pub const MAX_REPORT_PAYLOAD: u32 = 1024;
+
+/// Why a relay's response to a round request doesn't verify.
+#[derive(
+    Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, scale_info::TypeInfo, MaxEncodedLen,
+)]
+pub enum VerificationFailure {
+    /// The response isn't a round
+    Malformed,
+    /// The response is another round than the one requested
+    WrongRound { served: u64 },
+    /// The signature doesn't verify against the chain
+    InvalidSignature,
+    /// The randomness isn't derived from the signature
+    InvalidRandomness,
+    /// The round is validly signed, but for another chain, served by the relay
+    OtherChain {
+        chain_hash: BoundedVec<u8, ConstU32<32>>,
+    },
+}
+
+/// A reporter's claim that a relay served a round that doesn't verify.
+#[derive(
+    Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, scale_info::TypeInfo, MaxEncodedLen,
+)]
+pub struct MisbehaviourReport {
+    /// The misbehaving relay.
+    pub endpoint: Endpoint,
+    /// Hash of the chain the round was requested from.
+    pub chain_hash: BoundedVec<u8, ConstU32<32>>,
+    /// The requested round.
+    pub round: u64,
+    /// API version the payload was served with.
+    pub api_version: ApiVersion,
+    /// Body of the relay's response.
+    pub payload: BoundedVec<u8, ConstU32<MAX_REPORT_PAYLOAD>>,
+    pub failure: VerificationFailure,
+}
+
+impl MisbehaviourReport {
+    /// Whether the report is consistent with the chain `info`, i.e. its payload fails
+    /// verification the way it's reported. It doesn't tell that the relay served the payload.
+    /// Only the relay knows the other chain of an `OtherChain` report, so such a report holds as
+    /// long as its payload is a round that doesn't verify.
+    pub fn check(&self, info: &Info) -> bool {
+        if self.chain_hash != info.hash {
+            return false;
+        }
+        match (
+            verify_payload(self.api_version, info, self.round, &self.payload),
+            &self.failure,
+        ) {
+            (Ok(_), _) => false,
+            (
+                Err(VerificationFailure::InvalidSignature | VerificationFailure::InvalidRandomness),
+                VerificationFailure::OtherChain { .. },
+            ) => true,
+            (Err(failure), reported) => &failure == reported,
+        }
+    }
+}
+
+/// The relays reported by at least `quorum` distinct reporters, counting only the reports that
+/// check against the chain `info`. `reports` pairs each report with its reporter, e.g. the
+/// account that submitted it.
+pub fn reported_by_quorum<R: PartialEq>(
+    reports: &[(R, MisbehaviourReport)],
+    info: &Info,
+    quorum: usize,
+) -> Vec<Endpoint> {
+    let mut reporters: Vec<(&Endpoint, Vec<&R>)> = Vec::new();
+    for (reporter, report) in reports.iter().filter(|(_, report)| report.check(info)) {
+        match reporters
+            .iter_mut()
+            .find(|(endpoint, _)| *endpoint == &report.endpoint)
+        {
+            Some((_, seen)) if seen.contains(&reporter) => {}
+            Some((_, seen)) => seen.push(reporter),
+            None => reporters.push((&report.endpoint, sp_std::vec![reporter])),
+        }
+    }
+    reporters
+        .into_iter()
+        .filter(|(_, seen)| seen.len() >= quorum.max(1))
+        .map(|(endpoint, _)| endpoint.clone())
+        .collect()
+}
+
+/// Outcome of requesting a round from `endpoint` and every relay.
+#[derive(Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct CrossCheck {
+    /// The round, if at least one endpoint served it verified.
+    pub round: Option<Round>,
+    /// The endpoints that served a round that doesn't verify.
+    pub reports: Vec<MisbehaviourReport>,
+}
+
+impl Client {
+    /// Requests `round` from `endpoint` and every relay, and verifies each response on its own.
+    /// Endpoints that can't be reached aren't reported, nor responses over `MAX_REPORT_PAYLOAD`.
+    pub fn cross_check(&self, round: u64) -> Result<CrossCheck, ClientError> {
+        self.cross_check_with(&self.transport, round)
+    }
+
+    /// Same as `cross_check`, fetching through the given transport.
+    pub fn cross_check_with<T: Transport>(
+        &self,
+        transport: &T,
+        round: u64,
+    ) -> Result<CrossCheck, ClientError> {
+        let info = self.chain_info()?;
+        let mut check = CrossCheck {
+            round: None,
+            reports: Vec::new(),
+        };
+
+        for endpoint in self.endpoints() {
+            let relay = Client {
+                endpoint: endpoint.clone(),
+                ..self.clone()
+            };
+            let url = relay.url(&relay.path(ApiPath::Round(round)));
+            let payload = match relay.make_request_with(transport, url) {
+                Ok(payload) => payload,
+                Err(e) => {
+                    log::warn!("Skipping unreachable endpoint: {:?}", e);
+                    continue;
+                }
+            };
+
+            match verify_payload(self.api_version, &info, round, &payload) {
+                Ok(verified) => {
+                    check.round.get_or_insert(verified);
+                }
+                Err(_) if payload.len() > MAX_REPORT_PAYLOAD as usize => {
+                    log::warn!("Response too large to report, from {:?}", endpoint);
+                }
+                Err(failure) => {
+                    let failure = match failure {
+                        VerificationFailure::InvalidSignature
+                        | VerificationFailure::InvalidRandomness => relay
+                            .served_chain(transport, &info, &payload)
+                            .unwrap_or(failure),
+                        failure => failure,
+                    };
+                    log::warn!("Endpoint misbehaved on round {}: {:?}", round, failure);
+
+                    check.reports.push(MisbehaviourReport {
+                        endpoint: endpoint.clone(),
+                        chain_hash: info.hash.clone(),
+                        round,
+                        api_version: self.api_version,
+                        payload: payload.try_into().unwrap_or_default(),
+                        failure,
+                    });
+                }
+            }
+        }
+
+        Ok(check)
+    }
+
+    /// `OtherChain`, if the relay serves another chain, for which `payload` verifies.
+    fn served_chain<T: Transport>(
+        &self,
+        transport: &T,
+        info: &Info,
+        payload: &[u8],
+    ) -> Option<VerificationFailure> {
+        let round = self.parse_round(payload).ok()?;
+        let other = self.info_with(transport).ok()?;
+        if other.hash == info.hash || !other.has_valid_hash() {
+            return None;
+        }
+        Client::verify_round(&round, &other).ok()?;
+        Some(VerificationFailure::OtherChain {
+            chain_hash: other.hash,
+        })
+    }
+}
+
+/// The round in `payload`, if it is `round` of the chain `info`, and verifies.
+fn verify_payload(
+    api_version: ApiVersion,
+    info: &Info,
+    round: u64,
+    payload: &[u8],
+) -> Result<Round, VerificationFailure> {
+    let served = api_version
+        .parse_round(payload)
+        .map_err(|_| VerificationFailure::Malformed)?;
+    if served.round != round {
+        return Err(VerificationFailure::WrongRound {
+            served: served.round,
+        });
+    }
+
+    match Client::verify_round(&served, info) {
+        Ok(_) => Ok(served),
+        // also the error of signatures that don't decode
+        Err(ClientError::RandomnessVerificationError)
+            if served.randomness.as_slice() != derive_randomness(&served.signature).as_slice() =>
+        {
+            Err(VerificationFailure::InvalidRandomness)
+        }
+        Err(_) => Err(VerificationFailure::InvalidSignature),
+    }
+}
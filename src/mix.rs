@@ -0,0 +1,106 @@
+//! Randomness mixed from a verified drand round and a local source of the chain (e.g. BABE epoch
+//! randomness), exposed as a `frame_support` `Randomness` implementation.
+//!
+//! The output for a subject is
+//! `sha256("drand-substrate-client::mix" || len(subject) as u32 BE || subject || round number as
+//! u64 BE || drand randomness || SCALE encoded local output)`, decoded into the output type.
+//!
+//! Who can influence the result:
+//! - it is unpredictable until both inputs are known, so neither the drand network nor the block
+//!   authors can predict it alone;
+//! - the drand network can't bias its input (a round has a single valid signature), but could
+//!   withhold it;
+//! - the local source is as biasable as it is on its own: BABE block authors can, for instance,
+//!   skip their slot to discard an output;
+//! - whoever supplies the round to the runtime may choose among the rounds already emitted, so
+//!   `DrandRoundProvider` should only accept the round expected at the current time (see
+//!   `Info::round_at`), or a round fixed in advance;
+//! - as with any `Randomness`, the last party to reveal its input (usually the block author) can
+//!   refuse to and so choose between the output and its absence.
+//!
+//! Without a drand round, the output is only as good as the local source. `try_random` then
+//! returns `None`, and `random` reports block number zero: the output can't be used for any
+//! commitment, as `Randomness` requires outputs determined after the commitments they settle.
+
+use codec::{Decode, Encode};
+use frame_support::traits::Randomness;
+use sha2::{Digest, Sha256};
+use sp_runtime::traits::{TrailingZeroInput, Zero};
+use sp_std::marker::PhantomData;
+
+use crate::Round;
+
+/// Domain separation tag of the mix.
+pub const MIX_DOMAIN: &[u8] = b"drand-substrate-client::mix";
+
+/// The verified drand round to mix, e.g. read from the storage of a pallet collecting rounds.
+pub trait DrandRoundProvider {
+    /// The round to mix, or `None` if none is available yet.
+    fn round() -> Option<Round>;
+}
+
+/// `Randomness` mixing the round of `D` with the local source `L`.
+pub struct MixedRandomness<D, L>(PhantomData<(D, L)>);
+
+impl<D: DrandRoundProvider, L> MixedRandomness<D, L> {
+    /// Mixes the drand round with the local output for `subject`, or `None` if there is no
+    /// drand round. The block number is the one reported by the local source.
+    pub fn try_random<Output, BlockNumber>(subject: &[u8]) -> Option<(Output, BlockNumber)>
+    where
+        Output: Encode + Decode,
+        L: Randomness<Output, BlockNumber>,
+    {
+        let round = D::round()?;
+        let (local, block_number) = L::random(subject);
+        Some((
+            decode_output(&mix(subject, &round, &local.encode())),
+            block_number,
+        ))
+    }
+}
+
+impl<Output, BlockNumber, D, L> Randomness<Output, BlockNumber> for MixedRandomness<D, L>
+where
+    Output: Encode + Decode,
+    BlockNumber: Zero,
+    D: DrandRoundProvider,
+    L: Randomness<Output, BlockNumber>,
+{
+    /// Mixes the drand round with the local output for `subject`. The block number is the one
+    /// reported by the local source.
+    /// Without a drand round, the local output is mixed alone (as if with an empty round 0) and
+    /// the block number is zero, so that the output isn't used to settle any commitment.
+    fn random(subject: &[u8]) -> (Output, BlockNumber) {
+        if let Some(mixed) = Self::try_random(subject) {
+            return mixed;
+        }
+
+        log::warn!("No drand round to mix, the output can't be used");
+        let (local, _) = L::random(subject);
+        (
+            decode_output(&mix_parts(subject, 0, &[], &local.encode())),
+            BlockNumber::zero(),
+        )
+    }
+}
+
+/// Mixes `round` with the SCALE encoded `local` output for `subject`, so that anyone can recompute
+/// a `MixedRandomness` output off-chain.
+pub fn mix(subject: &[u8], round: &Round, local: &[u8]) -> [u8; 32] {
+    mix_parts(subject, round.round, &round.randomness, local)
+}
+
+fn decode_output<Output: Decode>(mixed: &[u8; 32]) -> Output {
+    Output::decode(&mut TrailingZeroInput::new(mixed)).expect("input is padded with zeroes; qed")
+}
+
+fn mix_parts(subject: &[u8], round: u64, randomness: &[u8], local: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(MIX_DOMAIN);
+    hasher.update((subject.len() as u32).to_be_bytes());
+    hasher.update(subject);
+    hasher.update(round.to_be_bytes());
+    hasher.update(randomness);
+    hasher.update(local);
+    hasher.finalize().into()
+}
@@ -0,0 +1,166 @@
+//! Randomness combined from several independent drand chains (e.g. the default mainnet chain and
+//! quicknet), as a hedge against the failure or compromise of a single one: the combined value is
+//! unpredictable as long as one of the constituent rounds is.
+//!
+//! For a timestamp, the round emitted at that time is fetched and verified on every chain, and
+//! the combined value is
+//! `sha256("drand-substrate-client::combine" || count as u32 BE || for each round, in the order of
+//! the chains: chain hash || round number as u64 BE || randomness)`.
+//! The constituent rounds are returned with it, so anyone can verify the result.
+//!
+//! Every chain must contribute its round emitted at the timestamp: otherwise whoever combines
+//! could pick, among recent rounds or subsets of the chains, the one giving the output they want.
+//! For the same reason, the timestamp must be fixed before its rounds are known, e.g. as the
+//! scheduled time of a block, and verifiers must check the combination against it.
+
+use codec::{Decode, Encode, MaxEncodedLen};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sp_runtime::{traits::ConstU32, BoundedVec, RuntimeDebug};
+use sp_std::vec::Vec;
+
+use crate::{Client, ClientError, Info, Round, Transport};
+
+/// Maximum number of chains combined by a `MultiClient`.
+pub const MAX_CHAINS: u32 = 8;
+
+/// Domain separation tag of the combination.
+pub const COMBINE_DOMAIN: &[u8] = b"drand-substrate-client::combine";
+
+/// Clients of several chains, each with its own endpoint, `chain_info` and cache settings.
+#[derive(
+    Encode,
+    Decode,
+    Clone,
+    PartialEq,
+    Eq,
+    RuntimeDebug,
+    scale_info::TypeInfo,
+    MaxEncodedLen,
+    Serialize,
+    Deserialize,
+)]
+pub struct MultiClient {
+    pub clients: BoundedVec<Client, ConstU32<MAX_CHAINS>>,
+}
+
+/// A round of one of the combined chains.
+#[derive(
+    Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, scale_info::TypeInfo, MaxEncodedLen,
+)]
+pub struct ChainRound {
+    pub chain_hash: BoundedVec<u8, ConstU32<32>>,
+    pub round: Round,
+}
+
+/// Combined randomness, with the constituent rounds as proof.
+#[derive(
+    Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, scale_info::TypeInfo, MaxEncodedLen,
+)]
+pub struct CombinedRandomness {
+    pub randomness: [u8; 32],
+    pub rounds: BoundedVec<ChainRound, ConstU32<MAX_CHAINS>>,
+}
+
+impl MultiClient {
+    /// Combines the chains of `clients`, which must all have `chain_info` set.
+    /// Returns `None` if there are more than `MAX_CHAINS` clients.
+    pub fn new(clients: Vec<Client>) -> Option<Self> {
+        Some(MultiClient {
+            clients: clients.try_into().ok()?,
+        })
+    }
+
+    /// Fetches and verifies the round emitted at `timestamp` (unix seconds) on each chain, and
+    /// combines them. Fails if any chain doesn't deliver its round. Each client fetches through
+    /// its own `transport` settings.
+    pub fn combined_at(&self, timestamp: u64) -> Result<CombinedRandomness, ClientError> {
+        self.combine_at(timestamp, |client, round| client.round(round))
+    }
+
+    /// Same as `combined_at`, fetching through the given transport.
+    pub fn combined_at_with<T: Transport>(
+        &self,
+        transport: &T,
+        timestamp: u64,
+    ) -> Result<CombinedRandomness, ClientError> {
+        self.combine_at(timestamp, |client, round| {
+            client.round_with(transport, round)
+        })
+    }
+
+    fn combine_at(
+        &self,
+        timestamp: u64,
+        fetch: impl Fn(&Client, u64) -> Result<Round, ClientError>,
+    ) -> Result<CombinedRandomness, ClientError> {
+        if self.clients.is_empty() {
+            return Err(ClientError::ChainNotConfigured);
+        }
+        let rounds = self
+            .clients
+            .iter()
+            .map(|client| {
+                let info = client.chain_info()?;
+                let round = fetch(client, info.round_at(timestamp))?;
+                Ok(ChainRound {
+                    chain_hash: info.hash,
+                    round,
+                })
+            })
+            .collect::<Result<Vec<_>, ClientError>>()?;
+
+        let randomness = combine(&rounds);
+        Ok(CombinedRandomness {
+            randomness,
+            rounds: rounds.try_into().map_err(|_| ClientError::Misc)?,
+        })
+    }
+
+    /// Verifies a combination of the rounds emitted at `timestamp` against the configured
+    /// chains.
+    pub fn verify(&self, combined: &CombinedRandomness, timestamp: u64) -> Result<(), ClientError> {
+        let chains = self
+            .clients
+            .iter()
+            .map(|client| client.chain_info())
+            .collect::<Result<Vec<_>, _>>()?;
+        combined.verify(&chains, timestamp)
+    }
+}
+
+impl CombinedRandomness {
+    /// Verifies that the combination has a round of every chain of `chains`, in that order, each
+    /// being the round emitted at `timestamp` and verifying against its chain, and the combined
+    /// value.
+    pub fn verify(&self, chains: &[Info], timestamp: u64) -> Result<(), ClientError> {
+        if chains.is_empty() || self.rounds.len() != chains.len() {
+            return Err(ClientError::RandomnessVerificationError);
+        }
+        for (chain_round, info) in self.rounds.iter().zip(chains) {
+            if chain_round.chain_hash != info.hash
+                || chain_round.round.round != info.round_at(timestamp)
+            {
+                return Err(ClientError::RandomnessVerificationError);
+            }
+            Client::verify_round(&chain_round.round, info)?;
+        }
+
+        if combine(&self.rounds) != self.randomness {
+            return Err(ClientError::RandomnessVerificationError);
+        }
+        Ok(())
+    }
+}
+
+fn combine(rounds: &[ChainRound]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(COMBINE_DOMAIN);
+    hasher.update((rounds.len() as u32).to_be_bytes());
+    for ChainRound { chain_hash, round } in rounds {
+        hasher.update(chain_hash);
+        hasher.update(round.round.to_be_bytes());
+        hasher.update(&round.randomness);
+    }
+    hasher.finalize().into()
+}
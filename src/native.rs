@@ -0,0 +1,115 @@
+//! Blocking HTTP transport for `std` consumers (collators, indexers, CLI tools), enabled with the
+//! `native-http` feature. It is backed by a `ureq::Agent`, which provides TLS (rustls),
+//! keep-alive connection reuse and timeouts.
+//! Redirects are returned as they are, for the `Client` to follow them with its own checks (same
+//! scheme, `MAX_REDIRECTS`, headers only sent to the origin).
+
+use std::{io::Read, time::Duration};
+
+use crate::{ClientError, HttpResponse, Transport, DEFAULT_MAX_BODY_SIZE};
+
+/// Default timeout for connecting and for the whole request, in line with the offchain transport.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// `Transport` using a native HTTP client. Cloning is cheap and clones share the connection pool.
+#[derive(Clone, Debug)]
+pub struct NativeTransport {
+    agent: ureq::Agent,
+    max_body_size: u32,
+}
+
+impl Default for NativeTransport {
+    fn default() -> Self {
+        Self::with_timeout(DEFAULT_TIMEOUT)
+    }
+}
+
+impl NativeTransport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a transport where connecting and the whole request must complete within `timeout`.
+    pub fn with_timeout(timeout: Duration) -> Self {
+        let agent = ureq::AgentBuilder::new()
+            .timeout_connect(timeout)
+            .timeout(timeout)
+            .redirects(0)
+            .build();
+        Self::from_agent(agent)
+    }
+
+    /// Uses an already configured agent, e.g. with a proxy or custom TLS settings.
+    /// The agent should not follow redirects (`AgentBuilder::redirects(0)`): ureq follows them by
+    /// default, bypassing the checks of the `Client` and sending the headers along.
+    pub fn from_agent(agent: ureq::Agent) -> Self {
+        NativeTransport {
+            agent,
+            max_body_size: DEFAULT_MAX_BODY_SIZE,
+        }
+    }
+
+    /// Sets the maximum size of a response body. Larger bodies fail with
+    /// `ClientError::ResponseTooLarge`.
+    pub fn with_max_body_size(mut self, max_body_size: u32) -> Self {
+        self.max_body_size = max_body_size;
+        self
+    }
+}
+
+impl Transport for NativeTransport {
+    fn get(&self, url: &str) -> Result<HttpResponse, ClientError> {
+        self.get_with_headers(url, &[])
+    }
+
+    fn get_with_headers(
+        &self,
+        url: &str,
+        headers: &[(String, String)],
+    ) -> Result<HttpResponse, ClientError> {
+        let request = headers
+            .iter()
+            .fold(self.agent.get(url), |request, (name, value)| {
+                request.set(name, value)
+            });
+        let response = match request.call() {
+            Ok(response) => response,
+            // non-2xx responses are still responses, the client decides what to do with them
+            Err(ureq::Error::Status(_, response)) => response,
+            Err(ureq::Error::Transport(e)) => {
+                log::warn!("HTTP request to {} failed: {}", url, e);
+                return Err(ClientError::Http);
+            }
+        };
+
+        let code = response.status();
+        let headers = response
+            .headers_names()
+            .into_iter()
+            .filter_map(|name| {
+                let value = response.header(&name)?.to_owned();
+                Some((name, value))
+            })
+            .collect();
+        let mut body = Vec::new();
+        // one byte past the limit tells a body at the limit from a larger one
+        response
+            .into_reader()
+            .take(self.max_body_size as u64 + 1)
+            .read_to_end(&mut body)
+            .map_err(|e| {
+                log::warn!("Failed to read response body: {}", e);
+                ClientError::Http
+            })?;
+        if body.len() > self.max_body_size as usize {
+            log::warn!("Response body is larger than {} bytes", self.max_body_size);
+            return Err(ClientError::ResponseTooLarge);
+        }
+
+        Ok(HttpResponse {
+            code,
+            body,
+            headers,
+        })
+    }
+}
@@ -0,0 +1,171 @@
+//! Registry of well-known drand networks, so a chain can be configured by name (e.g. from a
+//! runtime constant) or recognized by its chain hash, without trusting a relay for its info.
+//! Every network's info hashes to its chain hash, which is checked by the tests.
+//!
+//! `evmnet` uses the `bls-bn254-unchained-on-g1` scheme, whose rounds can't be verified by this
+//! crate: it is registered so the chain can be recognized, but fetching its rounds fails.
+//!
+//! Relays serve the `default` beacon of their network at their root, and every chain under its
+//! chain hash, which is how the endpoints of the other chains are built.
+
+extern crate alloc;
+
+use alloc::{format, string::String};
+use sp_runtime::RuntimeDebug;
+use sp_std::vec::Vec;
+
+use crate::{
+    util::{hex_to_vec_u8, vec_u8_to_hex},
+    Info, Scheme, DEFAULT_BEACON_ID,
+};
+
+/// Endpoint of `Client::default`.
+pub const MAINNET_ENDPOINT: &str = "https://drand.cloudflare.com";
+
+/// The other League of Entropy relays, serving the same chains as `MAINNET_ENDPOINT`.
+pub const MAINNET_RELAYS: [&str; 3] = [
+    "https://api.drand.sh",
+    "https://api2.drand.sh",
+    "https://api3.drand.sh",
+];
+
+/// League of Entropy testnet relays.
+pub const TESTNET_RELAYS: [&str; 2] = [
+    "https://pl-us.testnet.drand.sh",
+    "https://testnet0-api.drand.cloudflare.com",
+];
+
+/// A well-known drand chain, and the relays serving it.
+#[derive(Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub struct Network {
+    /// Name in the registry, e.g. `quicknet`.
+    pub name: &'static str,
+    /// Hex chain hash.
+    pub chain_hash: &'static str,
+    /// Hex compressed public key.
+    pub public_key: &'static str,
+    /// Seconds between rounds.
+    pub period: u64,
+    /// Unix time of round 1.
+    pub genesis_time: u64,
+    /// Hex group hash.
+    pub group_hash: &'static str,
+    pub scheme: Scheme,
+    pub beacon_id: &'static str,
+    /// Relays serving the network, the first one being requested first.
+    pub relays: &'static [&'static str],
+}
+
+/// League of Entropy mainnet `default` chain: chained, 30s rounds.
+pub const MAINNET: Network = Network {
+    name: "mainnet",
+    chain_hash: "8990e7a9aaed2ffed73dbd7092123d6f289930540d7651336225dc172e51b2ce",
+    public_key: "868f005eb8e6e4ca0a47c8a77ceaa5309a47978a7c71bc5cce96366b5d7a569937c529eeda66c7293784a9402801af31",
+    period: 30,
+    genesis_time: 1595431050,
+    group_hash: "176f93498eac9ca337150b46d21dd58673ea4e3581185f869672e59fa4cb390a",
+    scheme: Scheme::PedersenBlsChained,
+    beacon_id: "default",
+    relays: &[
+        MAINNET_ENDPOINT,
+        MAINNET_RELAYS[0],
+        MAINNET_RELAYS[1],
+        MAINNET_RELAYS[2],
+    ],
+};
+
+/// League of Entropy mainnet `quicknet` chain: unchained, 3s rounds, usable for timelock
+/// encryption.
+pub const QUICKNET: Network = Network {
+    name: "quicknet",
+    chain_hash: "52db9ba70e0cc0f6eaf7803dd07447a1f5477735fd3f661792ba94600c84e971",
+    public_key: "83cf0f2896adee7eb8b5f01fcad3912212c437e0073e911fb90022d3e760183c8c4b450b6a0a6c3ac6a5776a2d1064510d1fec758c921cc22b0e17e63aaf4bcb5ed66304de9cf809bd274ca73bab4af5a6e9c76a4bc09e76eae8991ef5ece45a",
+    period: 3,
+    genesis_time: 1692803367,
+    group_hash: "f477d5c89f21a17c863a7f937c6a6d15859414d2be09cd448d4279af331c5d3e",
+    scheme: Scheme::BlsUnchainedG1Rfc9380,
+    beacon_id: "quicknet",
+    relays: MAINNET.relays,
+};
+
+/// League of Entropy testnet counterpart of `QUICKNET`.
+pub const TESTNET_QUICKNET: Network = Network {
+    name: "quicknet-t",
+    chain_hash: "cc9c398442737cbd141526600919edd69f1d6f9b4adb67e4d912fbc64341a9a5",
+    public_key: "b15b65b46fb29104f6a4b5d1e11a8da6344463973d423661bb0804846a0ecd1ef93c25057f1c0baab2ac53e56c662b66072f6d84ee791a3382bfb055afab1e6a375538d8ffc451104ac971d2dc9b168e2d3246b0be2015969cbaac298f6502da",
+    period: 3,
+    genesis_time: 1689232296,
+    group_hash: "40d49d910472d4adb1d67f65db8332f11b4284eecf05c05c5eacd5eef7d40e2d",
+    scheme: Scheme::BlsUnchainedG1Rfc9380,
+    beacon_id: "quicknet-t",
+    relays: &TESTNET_RELAYS,
+};
+
+/// League of Entropy mainnet `evmnet` chain: unchained on BN254, 3s rounds, for verification by
+/// EVM contracts.
+pub const EVMNET: Network = Network {
+    name: "evmnet",
+    chain_hash: "04f1e9062b8a81f848fded9c12306733282b2727ecced50032187751166ec8c3",
+    public_key: "07e1d1d335df83fa98462005690372c643340060d205306a9aa8106b6bd0b3820557ec32c2ad488e4d4f6008f89a346f18492092ccc0d594610de2732c8b808f0095685ae3a85ba243747b1b2f426049010f6b73a0cf1d389351d5aaaa1047f6297d3a4f9749b33eb2d904c9d9ebf17224150ddd7abd7567a9bec6c74480ee0b",
+    period: 3,
+    genesis_time: 1727521075,
+    group_hash: "cd7ad2f0e0cce5d8c288f2dd016ffe7bc8dc88dbb229b3da2b6ad736490dfed6",
+    scheme: Scheme::BlsBn254UnchainedOnG1,
+    beacon_id: "evmnet",
+    relays: MAINNET.relays,
+};
+
+/// League of Entropy testnet `default` chain: chained, 25s rounds.
+pub const TESTNET: Network = Network {
+    name: "testnet",
+    chain_hash: "84b2234fb34e835dccd048255d7ad3194b81af7d978c3bf157e3469592ae4e02",
+    public_key: "922a2e93828ff83345bae533f5172669a26c02dc76d6bf59c80892e12ab1455c229211886f35bb56af6d5bea981024df",
+    period: 25,
+    genesis_time: 1590445175,
+    group_hash: "4dd408e5fdff9323c76a9b6f087ba8fdc5a6da907bd9217d9d10f2287d081957",
+    scheme: Scheme::PedersenBlsChained,
+    beacon_id: "default",
+    relays: &TESTNET_RELAYS,
+};
+
+/// All the networks of the registry.
+pub static NETWORKS: [Network; 5] = [MAINNET, QUICKNET, EVMNET, TESTNET, TESTNET_QUICKNET];
+
+impl Network {
+    /// The network named `name`.
+    pub fn by_name(name: &str) -> Option<&'static Network> {
+        NETWORKS.iter().find(|network| network.name == name)
+    }
+
+    /// The network of the chain `hash`.
+    pub fn by_chain_hash(hash: &[u8]) -> Option<&'static Network> {
+        NETWORKS
+            .iter()
+            .find(|network| network.chain_hash == vec_u8_to_hex(hash))
+    }
+
+    /// The chain info of the network.
+    pub fn info(&self) -> Info {
+        Info {
+            public_key: hex_to_vec_u8(self.public_key).unwrap().try_into().unwrap(),
+            period: self.period,
+            genesis_time: self.genesis_time,
+            hash: hex_to_vec_u8(self.chain_hash).unwrap().try_into().unwrap(),
+            group_hash: hex_to_vec_u8(self.group_hash).unwrap().try_into().unwrap(),
+            scheme: self.scheme,
+            beacon_id: self.beacon_id.as_bytes().to_vec().try_into().unwrap(),
+        }
+    }
+
+    /// Base URLs of the chain on each relay: the relay itself for the `default` beacon, else
+    /// under the chain hash.
+    pub fn endpoints(&self) -> Vec<String> {
+        self.relays
+            .iter()
+            .map(|relay| match self.beacon_id.as_bytes() {
+                DEFAULT_BEACON_ID => String::from(*relay),
+                _ => format!("{relay}/{}", self.chain_hash),
+            })
+            .collect()
+    }
+}
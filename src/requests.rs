@@ -0,0 +1,174 @@
+//! Requests of randomness from a future round, fulfilled once the round is emitted.
+//! Committing to a round that isn't emitted yet keeps anyone (including the requester) from
+//! knowing the outcome when the request is made.
+//!
+//! The pallet keeps the requests in its storage through `RequestStore`, and reacts to their
+//! outcome through `OnRandomness`:
+//! - `Requests::request` registers a request for a future round, from a dispatchable;
+//! - the offchain worker calls `Requests::fetch_due` to fetch the verified rounds of the requests
+//!   that are due, and submits them in transactions;
+//! - `Requests::fulfill` (from the dispatchable receiving a round) verifies the round and
+//!   fulfils every pending request of it;
+//! - `Requests::expire` (e.g. from `on_initialize`) times out the requests whose round wasn't
+//!   delivered in time, and `Requests::cancel` cancels a pending request.
+//!
+//! Each request gets its own randomness, derived from the round with `REQUEST_CONTEXT` and the
+//! request id (see `Round::derive`), so requests of the same round are independent.
+
+use codec::{Decode, Encode, MaxEncodedLen};
+use sp_runtime::RuntimeDebug;
+use sp_std::{collections::btree_set::BTreeSet, marker::PhantomData, vec::Vec};
+
+use crate::{Client, ClientError, Info, Round, Transport};
+
+/// Context of the randomness derived for each request.
+pub const REQUEST_CONTEXT: &[u8] = b"drand-substrate-client::request";
+
+/// A pending request of randomness.
+#[derive(
+    Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, scale_info::TypeInfo, MaxEncodedLen,
+)]
+pub struct RandomnessRequest {
+    pub id: u64,
+    /// The round whose randomness is requested.
+    pub round: u64,
+    /// Unix time (in seconds) after which the request times out if it isn't fulfilled.
+    pub expires_at: u64,
+}
+
+/// Request errors
+#[derive(PartialEq, Eq, RuntimeDebug)]
+pub enum RequestError {
+    /// The round is already emitted (or due), so its randomness may be known
+    RoundNotInFuture,
+    /// A request with this id is already pending
+    DuplicateRequest,
+    /// No pending request with this id, or for this round
+    UnknownRequest,
+    /// The store can't hold more requests
+    StoreFull,
+    /// The round doesn't verify against the chain
+    Verification(ClientError),
+}
+
+/// Storage of the pending requests, implemented by the pallet over its storage items.
+pub trait RequestStore {
+    /// Stores a new request.
+    fn insert(request: RandomnessRequest) -> Result<(), RequestError>;
+    fn get(id: u64) -> Option<RandomnessRequest>;
+    fn remove(id: u64) -> Option<RandomnessRequest>;
+    /// All the pending requests.
+    fn pending() -> Vec<RandomnessRequest>;
+}
+
+/// Outcome of the requests.
+pub trait OnRandomness {
+    /// The round of request `id` was delivered and verified. `randomness` is specific to the
+    /// request.
+    fn on_fulfilled(id: u64, round: &Round, randomness: [u8; 32]);
+    /// Request `id` wasn't fulfilled before it expired.
+    fn on_timeout(id: u64);
+    /// Request `id` was cancelled.
+    fn on_cancelled(id: u64);
+}
+
+/// The requests of the store `S`, notifying `H`.
+pub struct Requests<S, H>(PhantomData<(S, H)>);
+
+impl<S: RequestStore, H: OnRandomness> Requests<S, H> {
+    /// Requests the randomness of `round` of the chain `info`, which must not be due at `now`
+    /// (unix seconds). The request times out `timeout` seconds after the round is due.
+    pub fn request(
+        info: &Info,
+        id: u64,
+        round: u64,
+        timeout: u64,
+        now: u64,
+    ) -> Result<(), RequestError> {
+        if round <= info.round_at(now) {
+            return Err(RequestError::RoundNotInFuture);
+        }
+        if S::get(id).is_some() {
+            return Err(RequestError::DuplicateRequest);
+        }
+
+        S::insert(RandomnessRequest {
+            id,
+            round,
+            expires_at: info.round_time(round).saturating_add(timeout),
+        })
+    }
+
+    /// Cancels the pending request `id`.
+    pub fn cancel(id: u64) -> Result<(), RequestError> {
+        S::remove(id).ok_or(RequestError::UnknownRequest)?;
+        H::on_cancelled(id);
+        Ok(())
+    }
+
+    /// Verifies `round` against the chain `info`, and fulfils every pending request of it.
+    /// Returns the number of fulfilled requests.
+    pub fn fulfill(info: &Info, round: &Round) -> Result<u32, RequestError> {
+        let requests: Vec<_> = S::pending()
+            .into_iter()
+            .filter(|request| request.round == round.round)
+            .collect();
+        if requests.is_empty() {
+            return Err(RequestError::UnknownRequest);
+        }
+        Client::verify_round(round, info).map_err(RequestError::Verification)?;
+
+        for request in &requests {
+            S::remove(request.id);
+            H::on_fulfilled(request.id, round, round.derive(REQUEST_CONTEXT, request.id));
+        }
+        Ok(requests.len() as u32)
+    }
+
+    /// Times out the requests expired at `now` (unix seconds). Returns how many expired.
+    pub fn expire(now: u64) -> u32 {
+        let mut expired = 0;
+        for request in S::pending() {
+            if request.expires_at <= now {
+                S::remove(request.id);
+                H::on_timeout(request.id);
+                expired += 1;
+            }
+        }
+        expired
+    }
+
+    /// Rounds of the pending requests that are due at `now` (unix seconds), without duplicates.
+    pub fn due_rounds(info: &Info, now: u64) -> Vec<u64> {
+        S::pending()
+            .into_iter()
+            .filter(|request| info.round_time(request.round) <= now && now < request.expires_at)
+            .map(|request| request.round)
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    }
+
+    /// Fetches and verifies the rounds that are due at `now`, from the offchain worker.
+    /// Rounds that can't be fetched yet are skipped, to be retried by a later worker.
+    pub fn fetch_due(client: &Client, now: u64) -> Vec<Round> {
+        Self::fetch_due_with(client, &client.transport, now)
+    }
+
+    /// Same as `fetch_due`, fetching through the given transport.
+    pub fn fetch_due_with<T: Transport>(client: &Client, transport: &T, now: u64) -> Vec<Round> {
+        let Ok(info) = client.chain_info() else {
+            return Vec::new();
+        };
+        Self::due_rounds(&info, now)
+            .into_iter()
+            .filter_map(|round| match client.round_with(transport, round) {
+                Ok(round) => Some(round),
+                Err(e) => {
+                    log::warn!("Failed to fetch round {}: {:?}", round, e);
+                    None
+                }
+            })
+            .collect()
+    }
+}
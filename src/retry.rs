@@ -0,0 +1,190 @@
+//! Retries of failed requests, within an offchain worker run and across runs.
+//! Only failures of the endpoint itself are retried: requests that couldn't be completed, server
+//! errors (`5xx`) and `429 Too Many Requests`. Other responses, e.g. a `404` for a round that
+//! isn't emitted yet, come from a working endpoint and are returned as they are.
+//! With `retry` enabled, a request to `endpoint` is attempted up to `max_attempts` times, waiting
+//! between two attempts as between two failed runs. When every attempt fails, the endpoint is
+//! backed off: requests to it fail with `ClientError::RetryLater`, without going out, until a
+//! delay that doubles with each failed run (up to `max_delay_ms`) has passed. The delays are
+//! jittered with the node's local randomness, so the workers of different nodes don't retry in
+//! lockstep. The backoff is recorded in the offchain local storage (PERSISTENT kind), and cleared
+//! by the first request the endpoint answers.
+
+use codec::{Decode, Encode, MaxEncodedLen};
+use serde::{Deserialize, Serialize};
+use sp_runtime::{
+    offchain::{storage::StorageValueRef, Duration},
+    RuntimeDebug,
+};
+use sp_std::vec::Vec;
+
+use crate::{Client, ClientError, HttpResponse};
+
+/// Prefix of all the offchain storage keys of the retry states.
+pub const RETRY_KEY_PREFIX: &[u8] = b"drand-substrate-client::retry::";
+
+/// Configuration of the retries, part of the `Client` configuration.
+#[derive(
+    Encode,
+    Decode,
+    Clone,
+    PartialEq,
+    Eq,
+    RuntimeDebug,
+    scale_info::TypeInfo,
+    MaxEncodedLen,
+    Serialize,
+    Deserialize,
+)]
+pub struct RetryConfig {
+    /// Attempts of a request within a run (at least one). The attempts are spaced out like the
+    /// backoff across runs, so they hold the offchain worker for as long.
+    pub max_attempts: u32,
+    /// Backoff after the first failed run.
+    pub base_delay_ms: u64,
+    /// Longest backoff, before jitter.
+    pub max_delay_ms: u64,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        RetryConfig {
+            max_attempts: 2,
+            base_delay_ms: 3_000,
+            max_delay_ms: 300_000,
+        }
+    }
+}
+
+impl RetryConfig {
+    /// Backoff after `failures` consecutive failed runs: `base_delay_ms * 2^(failures - 1)`, up
+    /// to `max_delay_ms`, plus up to a quarter of it picked from `seed`.
+    pub fn delay(&self, failures: u32, seed: [u8; 32]) -> u64 {
+        let delay = self.backoff(failures);
+
+        let mut random = [0u8; 8];
+        random.copy_from_slice(&seed[..8]);
+        let jitter = u64::from_le_bytes(random) % (delay / 4 + 1);
+        delay.saturating_add(jitter)
+    }
+
+    /// Longest wait between the attempts of a request, i.e. with the most jitter.
+    pub fn max_wait_ms(&self) -> u64 {
+        let waits = self.max_attempts.saturating_sub(1);
+        let longest = |failures| {
+            let delay = self.backoff(failures);
+            delay.saturating_add(delay / 4)
+        };
+        // past 64 failures, the backoff doesn't grow anymore
+        let growing = (1..=waits.min(64))
+            .map(longest)
+            .fold(0, u64::saturating_add);
+        longest(64)
+            .saturating_mul(u64::from(waits.saturating_sub(64)))
+            .saturating_add(growing)
+    }
+
+    fn backoff(&self, failures: u32) -> u64 {
+        let exponent = failures.saturating_sub(1).min(63);
+        self.base_delay_ms
+            .saturating_mul(1 << exponent)
+            .min(self.max_delay_ms)
+    }
+}
+
+/// Backoff of an endpoint.
+#[derive(
+    Encode, Decode, Clone, Default, PartialEq, Eq, RuntimeDebug, scale_info::TypeInfo, MaxEncodedLen,
+)]
+pub struct RetryState {
+    /// Consecutive runs whose requests to the endpoint all failed.
+    pub failures: u32,
+    /// Unix time (in milliseconds) before which the endpoint isn't requested.
+    pub retry_at: u64,
+}
+
+impl RetryState {
+    /// Backoff of `endpoint`, none if its last request succeeded.
+    pub fn get(endpoint: &[u8]) -> RetryState {
+        StorageValueRef::persistent(&retry_key(endpoint))
+            .get()
+            .ok()
+            .flatten()
+            .unwrap_or_default()
+    }
+
+    fn set(&self, endpoint: &[u8]) {
+        StorageValueRef::persistent(&retry_key(endpoint)).set(self);
+    }
+
+    fn clear(endpoint: &[u8]) {
+        StorageValueRef::persistent(&retry_key(endpoint)).clear();
+    }
+}
+
+impl Client {
+    /// Enables (or disables, with `None`) the retries of failed requests.
+    pub fn set_retry(&mut self, retry: Option<RetryConfig>) {
+        self.retry = retry;
+    }
+
+    /// Runs `request` with the retry policy of the client, if enabled.
+    pub(crate) fn with_retries(
+        &self,
+        mut request: impl FnMut() -> Result<HttpResponse, ClientError>,
+    ) -> Result<HttpResponse, ClientError> {
+        let Some(config) = &self.retry else {
+            return request();
+        };
+
+        let now = sp_io::offchain::timestamp().unix_millis();
+        let mut state = RetryState::get(self.endpoint.as_bytes());
+        if now < state.retry_at {
+            log::warn!("Endpoint backed off until {}", state.retry_at);
+            return Err(ClientError::RetryLater);
+        }
+
+        let mut result = request();
+        for attempt in 1..config.max_attempts {
+            if !is_endpoint_failure(&result) {
+                break;
+            }
+            let delay = config.delay(attempt, sp_io::offchain::random_seed());
+            sp_io::offchain::sleep_until(
+                sp_io::offchain::timestamp().add(Duration::from_millis(delay)),
+            );
+            result = request();
+        }
+
+        if is_endpoint_failure(&result) {
+            let now = sp_io::offchain::timestamp().unix_millis();
+            state.failures = state.failures.saturating_add(1);
+            let delay = config.delay(state.failures, sp_io::offchain::random_seed());
+            state.retry_at = now.saturating_add(delay);
+            log::warn!(
+                "Request failed {} runs in a row, retrying in {}ms",
+                state.failures,
+                delay
+            );
+            state.set(self.endpoint.as_bytes());
+        } else if state.failures > 0 {
+            RetryState::clear(self.endpoint.as_bytes());
+        }
+        result
+    }
+}
+
+/// Whether the outcome of a request is a failure of its endpoint: the request couldn't be
+/// completed, or was answered with a server error (`5xx`) or `429 Too Many Requests`.
+pub(crate) fn is_endpoint_failure(result: &Result<HttpResponse, ClientError>) -> bool {
+    match result {
+        Ok(response) => response.code == 429 || response.code >= 500,
+        Err(_) => true,
+    }
+}
+
+fn retry_key(endpoint: &[u8]) -> Vec<u8> {
+    let mut key = RETRY_KEY_PREFIX.to_vec();
+    key.extend(endpoint);
+    key
+}
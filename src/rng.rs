@@ -0,0 +1,130 @@
+//! Randomness extraction: turns the randomness of a verified round into numbers, shuffles and
+//! samples, without the bias of ad-hoc `randomness % n` code.
+//!
+//! The algorithms are fixed and documented so that anyone can recompute a result off-chain from
+//! the round and the context:
+//! - the seed is `sha256("drand-substrate-client::rng" || len(context) as u32 BE || context ||
+//!   randomness)`, so different contexts (e.g. one per lottery) get independent streams;
+//! - the stream is the ChaCha20 (RFC 8439) keystream with the seed as key, a zero nonce and a
+//!   block counter starting at 0;
+//! - `next_u64` reads the next 8 bytes of the stream, little-endian;
+//! - `uniform(start..end)` draws `x = next_u64()` until `x < n·⌊(2^64 - 1) / n⌋` with
+//!   `n = end - start`, and returns `start + x mod n`;
+//! - `shuffle` is the forward Fisher–Yates: for `i` in `0..len - 1`, swap `i` with
+//!   `uniform(i..len)`;
+//! - `sample(k, n)` returns the first `k` elements of `shuffle` applied to `0..n`, without
+//!   allocating `n` elements, for `k` up to `MAX_SAMPLE_SIZE`;
+//! - `choose_weighted` draws `x = uniform(0..total)` and returns the first index whose
+//!   cumulative weight exceeds `x`.
+
+use chacha20::{
+    cipher::{KeyIvInit, StreamCipher},
+    ChaCha20,
+};
+use sha2::{Digest, Sha256};
+use sp_std::{collections::btree_map::BTreeMap, ops::Range, vec::Vec};
+
+use crate::Round;
+
+/// Domain separation tag of the seed derivation.
+pub const RNG_DOMAIN: &[u8] = b"drand-substrate-client::rng";
+
+/// Maximum number of elements drawn by `RoundRng::sample`.
+pub const MAX_SAMPLE_SIZE: u64 = 1 << 16;
+
+/// Deterministic RNG seeded from the randomness of a round and a context.
+pub struct RoundRng {
+    cipher: ChaCha20,
+}
+
+impl RoundRng {
+    /// RNG seeded from `randomness` and `context`.
+    pub fn new(randomness: &[u8], context: &[u8]) -> Self {
+        let mut hasher = Sha256::new();
+        hasher.update(RNG_DOMAIN);
+        hasher.update((context.len() as u32).to_be_bytes());
+        hasher.update(context);
+        hasher.update(randomness);
+        let seed: [u8; 32] = hasher.finalize().into();
+
+        RoundRng {
+            cipher: ChaCha20::new(&seed.into(), &[0u8; 12].into()),
+        }
+    }
+
+    /// RNG seeded from the randomness of `round`, which should have been verified (as the
+    /// `Client` does for the rounds it returns).
+    pub fn from_round(round: &Round, context: &[u8]) -> Self {
+        Self::new(&round.randomness, context)
+    }
+
+    /// Fills `dest` with the next bytes of the stream.
+    pub fn fill_bytes(&mut self, dest: &mut [u8]) {
+        dest.fill(0);
+        self.cipher.apply_keystream(dest);
+    }
+
+    /// Next 8 bytes of the stream, little-endian.
+    pub fn next_u64(&mut self) -> u64 {
+        let mut bytes = [0u8; 8];
+        self.fill_bytes(&mut bytes);
+        u64::from_le_bytes(bytes)
+    }
+
+    /// Unbiased number in `range`, or `None` if the range is empty.
+    pub fn uniform(&mut self, range: Range<u64>) -> Option<u64> {
+        let n = range.end.checked_sub(range.start).filter(|n| *n > 0)?;
+        let zone = (u64::MAX / n) * n;
+        loop {
+            let x = self.next_u64();
+            if x < zone {
+                return Some(range.start + x % n);
+            }
+        }
+    }
+
+    /// Shuffles `items` in place (forward Fisher–Yates).
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        let len = items.len() as u64;
+        for i in 0..len.saturating_sub(1) {
+            if let Some(j) = self.uniform(i..len) {
+                items.swap(i as usize, j as usize);
+            }
+        }
+    }
+
+    /// `k` distinct numbers of `0..n`, in the order they are drawn, or `None` if `k > n` or
+    /// `k > MAX_SAMPLE_SIZE`. Same result as shuffling `0..n` and keeping the first `k` elements.
+    pub fn sample(&mut self, k: u64, n: u64) -> Option<Vec<u64>> {
+        // checked before allocating `k` elements
+        if k > n || k > MAX_SAMPLE_SIZE {
+            return None;
+        }
+        // positions swapped so far, the others hold their own index
+        let mut swapped = BTreeMap::new();
+        let mut sample = Vec::with_capacity(k as usize);
+        for i in 0..k {
+            let j = self.uniform(i..n)?;
+            let at_j = *swapped.get(&j).unwrap_or(&j);
+            let at_i = *swapped.get(&i).unwrap_or(&i);
+            swapped.insert(j, at_i);
+            sample.push(at_j);
+        }
+        Some(sample)
+    }
+
+    /// Index drawn with probability proportional to its weight, or `None` if the weights are all
+    /// zero or their sum overflows.
+    pub fn choose_weighted(&mut self, weights: &[u64]) -> Option<usize> {
+        let total = weights
+            .iter()
+            .try_fold(0u64, |total, weight| total.checked_add(*weight))?;
+        let x = self.uniform(0..total)?;
+
+        let mut cumulative = 0;
+        weights.iter().position(|weight| {
+            cumulative += weight;
+            cumulative > x
+        })
+    }
+}
@@ -0,0 +1,181 @@
+//! Per-endpoint request statistics, and selection of the endpoint to use from them.
+//! With `stats` enabled, every request made by a `Client` records its outcome and latency
+//! against the client's `endpoint`, in the offchain local storage (PERSISTENT kind), so the
+//! statistics build up across offchain worker runs. As for retries, a request only fails if the
+//! endpoint did: it couldn't be completed, or was answered with a `5xx` or a `429`.
+//! `Client::preferred_endpoint` picks the fastest endpoint whose last requests succeeded, and
+//! lets an endpoint that keeps failing be probed again once every `probe_interval_ms`.
+
+use codec::{Decode, Encode, MaxEncodedLen};
+use serde::{Deserialize, Serialize};
+use sp_runtime::{offchain::storage::StorageValueRef, RuntimeDebug};
+use sp_std::vec::Vec;
+
+use crate::{Client, Endpoint};
+
+/// Prefix of all the offchain storage keys of the endpoint statistics.
+pub const STATS_KEY_PREFIX: &[u8] = b"drand-substrate-client::stats::";
+
+/// Configuration of the endpoint statistics, part of the `Client` configuration.
+#[derive(
+    Encode,
+    Decode,
+    Clone,
+    PartialEq,
+    Eq,
+    RuntimeDebug,
+    scale_info::TypeInfo,
+    MaxEncodedLen,
+    Serialize,
+    Deserialize,
+)]
+pub struct StatsConfig {
+    /// Consecutive failures after which an endpoint is avoided.
+    pub max_consecutive_failures: u32,
+    /// How long an avoided endpoint is left alone after its last failure, before it's probed.
+    pub probe_interval_ms: u64,
+}
+
+impl Default for StatsConfig {
+    fn default() -> Self {
+        StatsConfig {
+            max_consecutive_failures: 3,
+            probe_interval_ms: 60_000,
+        }
+    }
+}
+
+/// Requests made to an endpoint, and their outcome.
+#[derive(
+    Encode, Decode, Clone, Default, PartialEq, Eq, RuntimeDebug, scale_info::TypeInfo, MaxEncodedLen,
+)]
+pub struct EndpointStats {
+    pub requests: u32,
+    pub failures: u32,
+    /// Failures since the last successful request.
+    pub consecutive_failures: u32,
+    /// Moving average of the latency of successful requests, in milliseconds.
+    pub latency_ms: u64,
+    /// Unix time (in milliseconds) of the last failure, if any.
+    pub last_failure: Option<u64>,
+}
+
+impl EndpointStats {
+    /// Share of the requests that failed, in percent.
+    pub fn error_rate(&self) -> u32 {
+        match self.requests {
+            0 => 0,
+            requests => (self.failures as u64 * 100 / requests as u64) as u32,
+        }
+    }
+
+    /// Whether the endpoint should be used, rather than avoided.
+    pub fn is_healthy(&self, config: &StatsConfig) -> bool {
+        self.consecutive_failures < config.max_consecutive_failures
+    }
+
+    /// Whether an avoided endpoint is due for another try at `now` (unix milliseconds).
+    pub fn is_due_for_probe(&self, config: &StatsConfig, now: u64) -> bool {
+        match self.last_failure {
+            Some(last_failure) => last_failure.saturating_add(config.probe_interval_ms) <= now,
+            None => true,
+        }
+    }
+
+    /// Recorded statistics of `endpoint`, empty if it was never used.
+    pub fn get(endpoint: &[u8]) -> EndpointStats {
+        StorageValueRef::persistent(&stats_key(endpoint))
+            .get()
+            .ok()
+            .flatten()
+            .unwrap_or_default()
+    }
+
+    /// Records a request to `endpoint` that took `latency_ms`, made at `now` (unix milliseconds).
+    pub fn record(endpoint: &[u8], success: bool, latency_ms: u64, now: u64) {
+        let mut stats = EndpointStats::get(endpoint);
+        stats.update(success, latency_ms, now);
+        StorageValueRef::persistent(&stats_key(endpoint)).set(&stats);
+    }
+
+    fn update(&mut self, success: bool, latency_ms: u64, now: u64) {
+        self.requests = self.requests.saturating_add(1);
+        if success {
+            // the first latency is taken as is, then each request weighs 1/8
+            self.latency_ms = match self.requests - self.failures {
+                1 => latency_ms,
+                _ => (self.latency_ms.saturating_mul(7).saturating_add(latency_ms)) / 8,
+            };
+            self.consecutive_failures = 0;
+        } else {
+            self.failures = self.failures.saturating_add(1);
+            self.consecutive_failures = self.consecutive_failures.saturating_add(1);
+            self.last_failure = Some(now);
+        }
+    }
+}
+
+impl Client {
+    /// Enables (or disables, with `None`) the statistics of the requests to `endpoint`.
+    pub fn set_stats(&mut self, stats: Option<StatsConfig>) {
+        self.stats = stats;
+    }
+
+    /// Recorded statistics of `endpoint` and of each relay, in that order.
+    pub fn endpoint_stats(&self) -> Vec<(Endpoint, EndpointStats)> {
+        self.endpoints()
+            .map(|endpoint| (endpoint.clone(), EndpointStats::get(endpoint.as_bytes())))
+            .collect()
+    }
+
+    /// A client using the preferred one of `endpoint` and `relays`, from their statistics:
+    /// - an avoided endpoint due for a probe, so it gets a chance to recover;
+    /// - else the healthy endpoint with the lowest latency (endpoints never used first);
+    /// - else, when every endpoint is avoided, the one that failed the longest ago.
+    pub fn preferred_endpoint(&self) -> Client {
+        let now = sp_io::offchain::timestamp().unix_millis();
+        let config = self.stats.clone().unwrap_or_default();
+        let stats = self.endpoint_stats();
+
+        let probe = stats
+            .iter()
+            .find(|(_, stats)| !stats.is_healthy(&config) && stats.is_due_for_probe(&config, now));
+        let fastest = stats
+            .iter()
+            .filter(|(_, stats)| stats.is_healthy(&config))
+            .min_by_key(|(_, stats)| (stats.requests > 0, stats.latency_ms));
+        let least_recently_failed = || stats.iter().min_by_key(|(_, stats)| stats.last_failure);
+
+        match probe.or(fastest).or_else(least_recently_failed) {
+            Some((endpoint, _)) => Client {
+                endpoint: endpoint.clone(),
+                ..self.clone()
+            },
+            None => self.clone(),
+        }
+    }
+
+    /// Records the outcome of a request started at `started` (unix milliseconds), if enabled.
+    pub(crate) fn record_request(&self, started: u64, success: bool) {
+        if self.stats.is_some() {
+            let now = sp_io::offchain::timestamp().unix_millis();
+            EndpointStats::record(
+                self.endpoint.as_bytes(),
+                success,
+                now.saturating_sub(started),
+                now,
+            );
+        }
+    }
+
+    /// `endpoint`, then the relays.
+    pub(crate) fn endpoints(&self) -> impl Iterator<Item = &Endpoint> {
+        sp_std::iter::once(&self.endpoint).chain(self.relays.iter())
+    }
+}
+
+fn stats_key(endpoint: &[u8]) -> Vec<u8> {
+    let mut key = STATS_KEY_PREFIX.to_vec();
+    key.extend(endpoint);
+    key
+}
@@ -0,0 +1,109 @@
+//! v1 and v2 HTTP APIs.
+//! The v2 fixtures (`testdata/v2`) are not captured from `api.drand.sh/v2`: they are the v1
+//! fixtures (`chain_info.json`, `latest.json`) with the field names of the v2 API, as no capture
+//! could be made yet. They check the parsing of the v2 shapes, not what the relays serve.
+
+use std::fs;
+
+use super::local_client;
+use crate::{
+    api::ApiPath, ApiVersion, Client, ClientError, Info, InfoV2Raw, MockTransport, Round, RoundRaw,
+    RoundV2Raw,
+};
+
+const MAINNET_HASH: &str = "8990e7a9aaed2ffed73dbd7092123d6f289930540d7651336225dc172e51b2ce";
+
+fn testdata(name: &str) -> Vec<u8> {
+    fs::read(format!("./src/tests/testdata/{name}")).unwrap()
+}
+
+fn v2_client() -> Client {
+    let mut client = local_client();
+    client.set_api_version(ApiVersion::V2);
+    client
+}
+
+#[test]
+fn paths() {
+    let hash = [0xab; 32];
+    let v1 = local_client();
+    assert_eq!(v1.path(ApiPath::Latest), "/public/latest");
+    assert_eq!(v1.path(ApiPath::Round(5)), "/public/5");
+    assert_eq!(
+        v1.path(ApiPath::ChainInfo(&hash)),
+        format!("/{}/info", "ab".repeat(32))
+    );
+
+    let mut v2 = v2_client();
+    assert_eq!(v2.path(ApiPath::Chains), "/v2/chains");
+    assert_eq!(v2.path(ApiPath::Info), "/v2/beacons/default/info");
+    assert_eq!(
+        v2.path(ApiPath::Latest),
+        "/v2/beacons/default/rounds/latest"
+    );
+    assert_eq!(v2.path(ApiPath::Round(5)), "/v2/beacons/default/rounds/5");
+    assert_eq!(
+        v2.path(ApiPath::ChainInfo(&hash)),
+        format!("/v2/chains/{}/info", "ab".repeat(32))
+    );
+
+    v2.set_chain_info(Info {
+        beacon_id: b"quicknet".to_vec().try_into().unwrap(),
+        ..Info::default()
+    });
+    assert_eq!(v2.path(ApiPath::Round(5)), "/v2/beacons/quicknet/rounds/5");
+}
+
+#[test]
+fn v2_shapes_parse_into_the_same_types() {
+    let info_v2: InfoV2Raw = serde_json::from_slice(&testdata("v2/info.json")).unwrap();
+    let info = Info::from(info_v2);
+    assert_eq!(info, Info::default());
+    assert!(info.has_valid_hash());
+
+    let round_v2: RoundV2Raw = serde_json::from_slice(&testdata("v2/round.json")).unwrap();
+    let round_v1: RoundRaw = serde_json::from_slice(&testdata("latest.json")).unwrap();
+    // the randomness is derived from the signature
+    assert_eq!(Round::from(round_v2), Round::from(round_v1));
+}
+
+#[test]
+fn fetch_with_v2_api() {
+    let transport = MockTransport::new()
+        .with_response(
+            "http://localhost/v2/beacons/default/info",
+            &testdata("v2/info.json"),
+        )
+        .with_response(
+            "http://localhost/v2/beacons/default/rounds/2458190",
+            &testdata("v2/round.json"),
+        )
+        .with_response(
+            "http://localhost/v2/beacons/default/rounds/latest",
+            &testdata("v2/round.json"),
+        )
+        .with_response(
+            "http://localhost/v2/chains",
+            format!(r#"["{MAINNET_HASH}"]"#).as_bytes(),
+        )
+        .with_response(
+            &format!("http://localhost/v2/chains/{MAINNET_HASH}/info"),
+            &testdata("v2/info.json"),
+        );
+
+    let client = v2_client();
+    assert_eq!(client.info_with(&transport), Ok(Info::default()));
+    assert_eq!(
+        client.round_with(&transport, 2458190).unwrap().round,
+        2458190
+    );
+    assert_eq!(client.latest_with(&transport).unwrap().round, 2458190);
+    assert_eq!(client.bootstrap_with(&transport), Ok(vec![Info::default()]));
+
+    // v1 responses don't parse as v2
+    let transport = MockTransport::new().with_response(
+        "http://localhost/v2/beacons/default/info",
+        &testdata("chain_info.json"),
+    );
+    assert_eq!(client.info_with(&transport), Err(ClientError::Json));
+}
@@ -0,0 +1,152 @@
+//! `AsyncClient` with an in-memory async transport.
+
+use std::{
+    collections::HashMap,
+    fs,
+    sync::atomic::{AtomicU32, Ordering},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use futures::StreamExt;
+
+use super::local_client;
+use crate::{AsyncClient, AsyncTransport, ClientError, Endpoint, HttpResponse, Info, WatchConfig};
+
+/// Serves fixtures, failing the first `failures` requests like a relay lagging behind.
+#[derive(Default)]
+struct FixtureTransport {
+    responses: HashMap<String, HttpResponse>,
+    failures: AtomicU32,
+}
+
+impl FixtureTransport {
+    fn new() -> Self {
+        let latest = fs::read("./src/tests/testdata/latest.json").unwrap();
+        let info = fs::read("./src/tests/testdata/chain_info.json").unwrap();
+        let mut responses = HashMap::new();
+        responses.insert(
+            "http://localhost/public/latest".into(),
+            HttpResponse::ok(latest.clone()),
+        );
+        responses.insert(
+            "http://localhost/public/2458190".into(),
+            HttpResponse::ok(latest),
+        );
+        responses.insert("http://localhost/info".into(), HttpResponse::ok(info));
+        FixtureTransport {
+            responses,
+            ..Default::default()
+        }
+    }
+}
+
+#[async_trait]
+impl AsyncTransport for FixtureTransport {
+    async fn get(&self, url: &str) -> Result<HttpResponse, ClientError> {
+        if self
+            .failures
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |f| f.checked_sub(1))
+            .is_ok()
+        {
+            return Ok(HttpResponse {
+                code: 404,
+                body: vec![],
+                headers: vec![],
+            });
+        }
+        self.responses.get(url).cloned().ok_or(ClientError::Http)
+    }
+}
+
+fn fast_retries(max_attempts: u32) -> WatchConfig {
+    WatchConfig {
+        initial_backoff: Duration::from_millis(1),
+        max_backoff: Duration::from_millis(4),
+        max_attempts,
+    }
+}
+
+#[tokio::test]
+async fn async_info_and_round() {
+    let client = AsyncClient::new(local_client(), FixtureTransport::new());
+
+    assert_eq!(client.info().await.unwrap().period, 30);
+    assert_eq!(client.latest().await.unwrap().round, 2458190);
+    assert_eq!(client.round(2458190).await.unwrap().round, 2458190);
+    assert_eq!(client.round(2458191).await, Err(ClientError::Http));
+}
+
+#[tokio::test]
+async fn watch_yields_verified_rounds() {
+    let client = AsyncClient::new(local_client(), FixtureTransport::new())
+        .with_watch_config(fast_retries(2));
+
+    let rounds: Vec<_> = client.watch_from(2458190).take(2).collect().await;
+    assert_eq!(rounds[0].as_ref().unwrap().round, 2458190);
+    // round 2458191 is never served, the error is reported after the retries
+    assert_eq!(rounds[1], Err(ClientError::Http));
+}
+
+#[tokio::test]
+async fn watch_retries_late_round() {
+    let transport = FixtureTransport::new();
+    transport.failures.store(3, Ordering::SeqCst);
+    let client = AsyncClient::new(local_client(), transport).with_watch_config(fast_retries(4));
+
+    let round = client.watch_from(2458190).next().await.unwrap();
+    assert_eq!(round.unwrap().round, 2458190);
+    assert_eq!(client.transport().failures.load(Ordering::SeqCst), 0);
+}
+
+#[tokio::test]
+async fn watch_ends_without_chain() {
+    let mut client = local_client();
+    client.chain_info = None;
+    let client = AsyncClient::new(client, FixtureTransport::new());
+
+    let rounds: Vec<_> = client.watch().collect().await;
+    assert_eq!(rounds, vec![Err(ClientError::ChainNotConfigured)]);
+}
+
+#[tokio::test]
+async fn follows_redirects() {
+    let mut transport = FixtureTransport::new();
+    let redirect = |location: &str| HttpResponse {
+        code: 302,
+        body: vec![],
+        headers: vec![("Location".into(), location.into())],
+    };
+    transport.responses.insert(
+        "http://moved/public/latest".into(),
+        redirect("http://localhost/public/latest"),
+    );
+    transport.responses.insert(
+        "http://moved/public/2458190".into(),
+        redirect("https://localhost/public/2458190"),
+    );
+    let mut client = local_client();
+    client.endpoint = Endpoint::parse("http://moved").unwrap();
+    let client = AsyncClient::new(client, transport);
+
+    assert_eq!(client.latest().await.unwrap().round, 2458190);
+    // not to another scheme
+    assert_eq!(client.round(2458190).await, Err(ClientError::Http));
+}
+
+#[test]
+fn round_timing() {
+    let info = Info {
+        genesis_time: 1_000,
+        period: 30,
+        ..Info::default()
+    };
+
+    assert_eq!(info.round_at(999), 0);
+    assert_eq!(info.round_at(1_000), 1);
+    assert_eq!(info.round_at(1_029), 1);
+    assert_eq!(info.round_at(1_030), 2);
+    assert_eq!(info.round_time(1), 1_000);
+    assert_eq!(info.round_time(2), 1_030);
+    assert_eq!(info.round_at(info.round_time(42)), 42);
+}
@@ -0,0 +1,66 @@
+//! Gap detection and backfilling.
+
+use crate::{BackfillProgress, Client, ClientError, HttpResponse, MockTransport, Scheme};
+
+use super::tlock::{round_json, sign, test_chain};
+
+#[test]
+fn progress() {
+    let info = test_chain(Scheme::PedersenBlsUnchained);
+    let mut progress = BackfillProgress::starting_at(10);
+    assert!(!progress.is_available(10));
+    assert_eq!(progress.missing(&info, info.round_time(14) + 1), 10..=14);
+    assert!(progress.missing(&info, info.round_time(9)).is_empty());
+
+    // 13 comes after a gap
+    let rounds: Vec<_> = [11, 10, 13].iter().map(|n| sign(&info, *n)).collect();
+    assert_eq!(progress.advance(&rounds), 2);
+    assert!(progress.is_available(11));
+    assert!(!progress.is_available(12));
+    assert_eq!(progress.missing(&info, info.round_time(14)), 12..=14);
+
+    assert_eq!(progress.advance(&[sign(&info, 12), sign(&info, 13)]), 2);
+    assert_eq!(progress.last_round, 13);
+}
+
+#[test]
+fn backfill_in_batches() {
+    let info = test_chain(Scheme::BlsUnchainedG1Rfc9380);
+    let client = Client::new("http://localhost", info.clone()).unwrap();
+    let now = info.round_time(20);
+
+    // round 14 isn't served (e.g. the relay is lagging)
+    let mut transport = MockTransport::new();
+    for n in (10..14).chain(15..=20) {
+        transport.insert(
+            &format!("http://localhost/public/{n}"),
+            HttpResponse::ok(round_json(&sign(&info, n))),
+        );
+    }
+
+    let mut progress = BackfillProgress::starting_at(10);
+    let batch = client.backfill_with(&transport, &progress, now, 3).unwrap();
+    assert_eq!(
+        batch.iter().map(|r| r.round).collect::<Vec<_>>(),
+        [10, 11, 12]
+    );
+    progress.advance(&batch);
+
+    // stops before the gap, without skipping it
+    let batch = client.backfill_with(&transport, &progress, now, 3).unwrap();
+    assert_eq!(batch.iter().map(|r| r.round).collect::<Vec<_>>(), [13]);
+    progress.advance(&batch);
+
+    assert_eq!(
+        client.backfill_with(&transport, &progress, now, 3),
+        Err(ClientError::Http)
+    );
+    assert_eq!(progress.last_round, 13);
+
+    // nothing to do once caught up
+    let progress = BackfillProgress::starting_at(21);
+    assert_eq!(
+        client.backfill_with(&transport, &progress, now, 3),
+        Ok(vec![])
+    );
+}
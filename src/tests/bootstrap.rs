@@ -0,0 +1,158 @@
+//! Chain listing, chain hash verification and pinning.
+
+use std::fs;
+
+use super::local_client;
+use crate::{
+    util::vec_u8_to_hex, Chains, ChainsRaw, ClientBuilder, ClientError, HttpResponse, Info,
+    InfoRaw, MockTransport, Scheme,
+};
+
+use super::tlock::test_chain;
+
+const MAINNET_HASH: &str = "8990e7a9aaed2ffed73dbd7092123d6f289930540d7651336225dc172e51b2ce";
+const QUICKNET_HASH: &str = "52db9ba70e0cc0f6eaf7803dd07447a1f5477735fd3f661792ba94600c84e971";
+
+pub(super) fn info_json(info: &Info) -> Vec<u8> {
+    serde_json::json!({
+        "public_key": vec_u8_to_hex(&info.public_key),
+        "period": info.period,
+        "genesis_time": info.genesis_time,
+        "hash": vec_u8_to_hex(&info.hash),
+        "groupHash": vec_u8_to_hex(&info.group_hash),
+        "schemeID": info.scheme.id(),
+        "metadata": { "beaconID": String::from_utf8(info.beacon_id.to_vec()).unwrap() },
+    })
+    .to_string()
+    .into_bytes()
+}
+
+/// A valid unchained chain, with its own beacon id.
+fn unchained_chain() -> Info {
+    let mut info = test_chain(Scheme::BlsUnchainedG1Rfc9380);
+    info.beacon_id = b"test".to_vec().try_into().unwrap();
+    info.hash = info.compute_hash().to_vec().try_into().unwrap();
+    info
+}
+
+#[test]
+fn chains_is_an_array() {
+    let raw: ChainsRaw = serde_json::from_str(&format!(
+        r#"["{MAINNET_HASH}", "not hex", "{}"]"#,
+        "ab".repeat(32)
+    ))
+    .unwrap();
+    let chains = Chains::from(raw);
+    assert_eq!(chains.hashes.len(), 2);
+    assert_eq!(vec_u8_to_hex(&chains.hashes[0]), MAINNET_HASH);
+}
+
+#[test]
+fn chain_hash() {
+    let raw: InfoRaw =
+        serde_json::from_slice(&fs::read("./src/tests/testdata/chain_info.json").unwrap()).unwrap();
+    let mainnet = Info::from(raw);
+    assert_eq!(mainnet.beacon_id.as_slice(), b"default");
+    assert!(mainnet.has_valid_hash());
+    assert_eq!(mainnet, Info::default());
+
+    assert!(unchained_chain().has_valid_hash());
+
+    let tampered = Info {
+        period: 3,
+        ..mainnet
+    };
+    assert!(!tampered.has_valid_hash());
+}
+
+#[test]
+fn quicknet_chain_hash() {
+    // as served by https://api.drand.sh/{QUICKNET_HASH}/info
+    let raw: InfoRaw = serde_json::from_str(&format!(
+        r#"{{
+            "public_key": "83cf0f2896adee7eb8b5f01fcad3912212c437e0073e911fb90022d3e760183c8c4b450b6a0a6c3ac6a5776a2d1064510d1fec758c921cc22b0e17e63aaf4bcb5ed66304de9cf809bd274ca73bab4af5a6e9c76a4bc09e76eae8991ef5ece45a",
+            "period": 3,
+            "genesis_time": 1692803367,
+            "hash": "{QUICKNET_HASH}",
+            "groupHash": "f477d5c89f21a17c863a7f937c6a6d15859414d2be09cd448d4279af331c5d3e",
+            "schemeID": "bls-unchained-g1-rfc9380",
+            "metadata": {{ "beaconID": "quicknet" }}
+        }}"#
+    ))
+    .unwrap();
+    let quicknet = Info::from(raw);
+    assert_eq!(quicknet.scheme, Scheme::BlsUnchainedG1Rfc9380);
+    assert_eq!(vec_u8_to_hex(&quicknet.compute_hash()), QUICKNET_HASH);
+    assert!(quicknet.has_valid_hash());
+}
+
+#[test]
+fn bootstrap_and_pin() {
+    let unchained = unchained_chain();
+    let mut bogus = unchained_chain();
+    bogus.period = 60;
+
+    let mut transport = MockTransport::new()
+        .with_response(
+            "http://localhost/chains",
+            format!(
+                r#"["{MAINNET_HASH}", "{}", "{}"]"#,
+                vec_u8_to_hex(&unchained.hash),
+                "ab".repeat(32)
+            )
+            .as_bytes(),
+        )
+        .with_response(
+            &format!("http://localhost/{MAINNET_HASH}/info"),
+            &fs::read("./src/tests/testdata/chain_info.json").unwrap(),
+        )
+        .with_response(
+            &format!("http://localhost/{}/info", vec_u8_to_hex(&unchained.hash)),
+            &info_json(&unchained),
+        );
+    // the third chain serves info that doesn't hash to its hash
+    transport.insert(
+        &format!("http://localhost/{}/info", "ab".repeat(32)),
+        HttpResponse::ok(info_json(&Info {
+            hash: vec![0xab; 32].try_into().unwrap(),
+            ..bogus
+        })),
+    );
+
+    let client = local_client();
+    let chains = client.bootstrap_with(&transport).unwrap();
+    assert_eq!(chains, vec![Info::default(), unchained.clone()]);
+    assert_eq!(
+        client.chain_info_of_with(&transport, &[0xab; 32]),
+        Err(ClientError::ChainHashMismatch)
+    );
+
+    // trust on first use
+    let mut client = ClientBuilder::new()
+        .with_endpoint("http://localhost")
+        .build()
+        .unwrap();
+    client.pin_chain(unchained.clone()).unwrap();
+    client.pin_chain(unchained.clone()).unwrap();
+    assert_eq!(
+        client.pin_chain(Info::default()),
+        Err(ClientError::ChainAlreadyPinned)
+    );
+    assert_eq!(client.chain_info(), Ok(unchained));
+
+    let mut tampered = Info::default();
+    tampered.genesis_time += 1;
+    assert_eq!(
+        ClientBuilder::new()
+            .with_endpoint("http://localhost")
+            .build()
+            .unwrap()
+            .pin_chain(tampered),
+        Err(ClientError::ChainHashMismatch)
+    );
+    // a client made with a constructor already has a chain
+    assert_eq!(
+        local_client().pin_chain(unchained_chain()),
+        Err(ClientError::ChainAlreadyPinned)
+    );
+}
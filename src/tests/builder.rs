@@ -0,0 +1,295 @@
+//! Building clients, from scratch and from the League of Entropy presets.
+
+use std::fs;
+
+use sp_io::TestExternalities;
+use sp_runtime::offchain::{testing, OffchainDbExt, OffchainWorkerExt};
+
+use super::{bootstrap::info_json, tlock::test_chain};
+use crate::{
+    api::ApiPath,
+    util::{hex_to_vec_u8, vec_u8_to_hex},
+    ApiVersion, CacheConfig, Client, ClientBuilder, ClientError, HeaderValue, HttpResponse, Info,
+    LockConfig, MockTransport, RetryConfig, Scheme, Verification, MAINNET_ENDPOINT,
+};
+
+const QUICKNET_HASH: &str = "52db9ba70e0cc0f6eaf7803dd07447a1f5477735fd3f661792ba94600c84e971";
+
+#[test]
+fn presets() {
+    let mainnet = ClientBuilder::mainnet().build().unwrap();
+    assert_eq!(mainnet.endpoint.as_str(), MAINNET_ENDPOINT);
+    assert_eq!(mainnet.relays.len(), 3);
+    assert_eq!(mainnet.chain_info, Some(Info::default()));
+
+    let quicknet = ClientBuilder::quicknet().build().unwrap();
+    let info = quicknet.chain_info().unwrap();
+    assert!(info.has_valid_hash());
+    assert_eq!(vec_u8_to_hex(&info.hash), QUICKNET_HASH);
+    assert_eq!(info.scheme, Scheme::BlsUnchainedG1Rfc9380);
+    assert_eq!(
+        quicknet.relays[0].as_str(),
+        format!("https://api.drand.sh/{QUICKNET_HASH}")
+    );
+}
+
+#[test]
+fn chains_under_their_hash() {
+    let hash = hex_to_vec_u8(QUICKNET_HASH).unwrap();
+    let client = ClientBuilder::new()
+        .with_endpoint("https://api.drand.sh")
+        .with_relays(&["https://api2.drand.sh/"])
+        .with_chain_hash(&hash)
+        .build()
+        .unwrap();
+    assert_eq!(
+        client.endpoint.as_str(),
+        format!("https://api.drand.sh/{QUICKNET_HASH}")
+    );
+    assert_eq!(
+        client.relays[0].as_str(),
+        format!("https://api2.drand.sh/{QUICKNET_HASH}")
+    );
+    assert_eq!(
+        client.url(&client.path(ApiPath::Latest)),
+        format!("https://api.drand.sh/{QUICKNET_HASH}/public/latest").into_bytes()
+    );
+
+    // not twice, and not for the `default` chain
+    let quicknet = ClientBuilder::quicknet().build().unwrap();
+    assert_eq!(
+        quicknet.endpoint.as_str(),
+        format!("{MAINNET_ENDPOINT}/{QUICKNET_HASH}")
+    );
+    let mainnet = ClientBuilder::mainnet().build().unwrap();
+    assert_eq!(mainnet.endpoint.as_str(), MAINNET_ENDPOINT);
+
+    // the v2 API addresses the chain from the root of the relay
+    let v2 = ClientBuilder::quicknet()
+        .with_api_version(ApiVersion::V2)
+        .build()
+        .unwrap();
+    assert_eq!(
+        v2.url(&v2.path(ApiPath::Latest)),
+        format!("{MAINNET_ENDPOINT}/v2/beacons/quicknet/rounds/latest").into_bytes()
+    );
+    let v2 = ClientBuilder::new()
+        .with_endpoint("https://api.drand.sh")
+        .with_chain_hash(&hash)
+        .with_api_version(ApiVersion::V2)
+        .build()
+        .unwrap();
+    assert_eq!(v2.endpoint.as_str(), "https://api.drand.sh");
+    assert_eq!(
+        v2.url(&v2.path(ApiPath::Round(5))),
+        b"https://api.drand.sh/v2/beacons/quicknet/rounds/5".to_vec()
+    );
+}
+
+#[test]
+fn build_with_settings() {
+    let client = ClientBuilder::mainnet()
+        .with_endpoint("http://localhost/")
+        .with_relays(&["http://relay"])
+        .with_scheme(Scheme::PedersenBlsChained)
+        .with_timeout(500)
+        .with_read_timeout(1_500)
+        .with_cache(CacheConfig::default())
+        .with_lock(LockConfig::default())
+        .with_header(
+            "http://relay",
+            "Authorization",
+            HeaderValue::Secret(b"drand::relay-token".to_vec().try_into().unwrap()),
+        )
+        .with_user_agent("drand-substrate-client/test")
+        .with_verification(Verification::Strict)
+        .build()
+        .unwrap();
+
+    assert_eq!(client.endpoint.as_str(), "http://localhost");
+    assert_eq!(client.relays[0].as_str(), "http://relay");
+    assert_eq!(client.transport.timeout_ms, 500);
+    assert_eq!(client.transport.read_timeout_ms, 1_500);
+    assert_eq!(client.cache, Some(CacheConfig::default()));
+    assert_eq!(client.lock, Some(LockConfig::default()));
+    assert_eq!(client.headers.len(), 2);
+    assert_eq!(client.verification, Verification::Strict);
+}
+
+#[test]
+fn headers_of_relays_under_the_chain_hash() {
+    let (offchain, _state) = testing::TestOffchainExt::new();
+    let mut t = TestExternalities::default();
+    t.register_extension(OffchainDbExt::new(offchain.clone()));
+    t.register_extension(OffchainWorkerExt::new(offchain));
+
+    let url = format!("https://api.drand.sh/{QUICKNET_HASH}/public/latest");
+    let transport = MockTransport::new().with_response(&url, b"{}");
+
+    t.execute_with(|| {
+        // keyed by the base URL of the relay, which the preset serves the chain under
+        let client = ClientBuilder::quicknet()
+            .with_header(
+                "https://api.drand.sh",
+                "Authorization",
+                HeaderValue::Plain(b"Bearer t0k3n".to_vec().try_into().unwrap()),
+            )
+            .build()
+            .unwrap();
+        let relay = Client {
+            endpoint: client.relays[0].clone(),
+            ..client
+        };
+        relay.make_request_with(&transport, url.into()).unwrap();
+
+        assert_eq!(
+            transport.sent_headers(),
+            vec![vec![(
+                "Authorization".to_string(),
+                "Bearer t0k3n".to_string()
+            )]]
+        );
+    })
+}
+
+#[test]
+fn reject_inconsistent_configurations() {
+    let local = || ClientBuilder::new().with_endpoint("http://localhost");
+
+    assert_eq!(
+        ClientBuilder::new()
+            .with_chain_info(Info::default())
+            .build(),
+        Err(ClientError::InvalidConfiguration)
+    );
+    assert_eq!(
+        local().with_relays(&["relay"]).build(),
+        Err(ClientError::InvalidEndpoint)
+    );
+    assert_eq!(
+        ClientBuilder::mainnet()
+            .with_lock(LockConfig::default())
+            .build(),
+        Err(ClientError::InvalidConfiguration)
+    );
+    assert_eq!(
+        local().with_cache(CacheConfig::default()).build(),
+        Err(ClientError::ChainNotConfigured)
+    );
+    assert_eq!(
+        local().with_verification(Verification::Strict).build(),
+        Err(ClientError::ChainNotConfigured)
+    );
+    assert_eq!(
+        ClientBuilder::mainnet()
+            .with_scheme(Scheme::BlsUnchainedG1Rfc9380)
+            .build(),
+        Err(ClientError::InvalidConfiguration)
+    );
+    assert_eq!(
+        ClientBuilder::mainnet()
+            .with_chain_hash(&[0xab; 32])
+            .build(),
+        Err(ClientError::ChainHashMismatch)
+    );
+    assert_eq!(
+        local()
+            .with_chain_info(Info {
+                period: 3,
+                ..Info::default()
+            })
+            .build(),
+        Err(ClientError::ChainHashMismatch)
+    );
+
+    // a lock expiring while the fetch still runs: 2 attempts of up to 4s, and up to 3.75s between
+    let locked = || {
+        ClientBuilder::mainnet()
+            .with_cache(CacheConfig::default())
+            .with_lock(LockConfig::Time {
+                expiration_ms: 11_750,
+            })
+    };
+    assert!(locked().build().is_ok());
+    assert!(locked().with_retry(RetryConfig::default()).build().is_ok());
+    assert_eq!(
+        locked()
+            .with_retry(RetryConfig::default())
+            .with_read_timeout(2_001)
+            .build(),
+        Err(ClientError::InvalidConfiguration)
+    );
+    assert_eq!(
+        ClientBuilder::mainnet()
+            .with_cache(CacheConfig::default())
+            .with_lock(LockConfig::default())
+            .with_retry(RetryConfig::default())
+            .build(),
+        Err(ClientError::InvalidConfiguration)
+    );
+
+    // a header that would never be sent
+    assert_eq!(
+        ClientBuilder::quicknet()
+            .with_header(
+                "https://unknown.example",
+                "Authorization",
+                HeaderValue::Plain(b"Bearer t0k3n".to_vec().try_into().unwrap()),
+            )
+            .build(),
+        Err(ClientError::InvalidConfiguration)
+    );
+
+    // a client without chain is fine, e.g. to bootstrap one
+    assert_eq!(local().build().unwrap().chain_info, None);
+}
+
+#[test]
+fn fetch_chain_info_of_pinned_hash() {
+    // a chain that isn't in the registry of networks
+    let mut info = test_chain(Scheme::BlsUnchainedG1Rfc9380);
+    info.beacon_id = b"test".to_vec().try_into().unwrap();
+    info.hash = info.compute_hash().to_vec().try_into().unwrap();
+    let url = format!("http://localhost/{}/info", vec_u8_to_hex(&info.hash));
+    let mut transport = MockTransport::new().with_response(&url, &info_json(&info));
+
+    let client = ClientBuilder::new()
+        .with_endpoint("http://localhost")
+        .with_chain_hash(&info.hash)
+        .build_with(&transport)
+        .unwrap();
+    assert_eq!(client.chain_info, Some(info.clone()));
+    assert_eq!(transport.requests(), vec![url]);
+
+    // the served info must hash to the pinned hash
+    transport.insert(
+        &format!("http://localhost/{}/info", "ab".repeat(32)),
+        HttpResponse::ok(info_json(&info)),
+    );
+    assert_eq!(
+        ClientBuilder::new()
+            .with_endpoint("http://localhost")
+            .with_chain_hash(&[0xab; 32])
+            .build_with(&transport),
+        Err(ClientError::ChainHashMismatch)
+    );
+}
+
+#[test]
+fn strict_verification_of_latest() {
+    let latest = fs::read_to_string("./src/tests/testdata/latest.json")
+        .unwrap()
+        .replace("2458190", "2458191");
+    let transport =
+        MockTransport::new().with_response("http://localhost/public/latest", latest.as_bytes());
+
+    let builder = ClientBuilder::mainnet().with_endpoint("http://localhost");
+    let standard = builder.clone().build().unwrap();
+    assert_eq!(standard.latest_with(&transport).unwrap().round, 2458191);
+
+    let strict = builder
+        .with_verification(Verification::Strict)
+        .build()
+        .unwrap();
+    assert!(strict.latest_with(&transport).is_err());
+}
@@ -0,0 +1,125 @@
+//! Offchain local-storage cache of verified rounds.
+
+use std::fs;
+
+use sp_io::TestExternalities;
+use sp_runtime::offchain::{testing, OffchainDbExt, OffchainWorkerExt, Timestamp};
+
+use super::local_client;
+use crate::{CacheConfig, Client, ClientError, Info, MockTransport, Round, RoundCache};
+
+const LATEST_URI: &str = "http://localhost/public/latest";
+const ROUND_URI: &str = "http://localhost/public/2458190";
+
+fn cached_client() -> Client {
+    let mut client = local_client();
+    client.set_cache(Some(CacheConfig::default()));
+    client
+}
+
+fn latest_body() -> Vec<u8> {
+    fs::read("./src/tests/testdata/latest.json").unwrap()
+}
+
+fn empty_round(round: u64) -> Round {
+    Round {
+        round,
+        randomness: Default::default(),
+        previous_signature: Default::default(),
+        signature: Default::default(),
+    }
+}
+
+#[test]
+fn round_is_fetched_once() {
+    let (offchain, _state) = testing::TestOffchainExt::new();
+    let mut t = TestExternalities::default();
+    t.register_extension(OffchainDbExt::new(offchain));
+
+    let transport = MockTransport::new().with_response(ROUND_URI, &latest_body());
+
+    t.execute_with(|| {
+        let client = cached_client();
+        let first = client.round_with(&transport, 2458190).unwrap();
+        let second = client.round_with(&transport, 2458190).unwrap();
+        assert_eq!(first, second);
+        assert_eq!(transport.requests().len(), 1);
+    })
+}
+
+#[test]
+fn latest_is_cached_until_next_round() {
+    let (offchain, state) = testing::TestOffchainExt::new();
+    let mut t = TestExternalities::default();
+    t.register_extension(OffchainDbExt::new(offchain.clone()));
+    t.register_extension(OffchainWorkerExt::new(offchain));
+
+    let transport = MockTransport::new().with_response(LATEST_URI, &latest_body());
+    let info = Info::default();
+
+    t.execute_with(|| {
+        let client = cached_client();
+        state.write().timestamp = Timestamp::from_unix_millis(info.round_time(2458190) * 1_000);
+
+        assert_eq!(client.latest_with(&transport).unwrap().round, 2458190);
+        assert_eq!(client.latest_with(&transport).unwrap().round, 2458190);
+        // the latest round is also available by number
+        assert_eq!(
+            client.round_with(&transport, 2458190).unwrap().round,
+            2458190
+        );
+        assert_eq!(transport.requests().len(), 1);
+
+        // the next round is due, ask the endpoint again
+        state.write().timestamp = Timestamp::from_unix_millis(info.round_time(2458191) * 1_000);
+        assert_eq!(client.latest_with(&transport).unwrap().round, 2458190);
+        assert_eq!(transport.requests().len(), 2);
+    })
+}
+
+#[test]
+fn unverified_latest_is_not_cached() {
+    let (offchain, _state) = testing::TestOffchainExt::new();
+    let mut t = TestExternalities::default();
+    t.register_extension(OffchainDbExt::new(offchain.clone()));
+    t.register_extension(OffchainWorkerExt::new(offchain));
+
+    let body = String::from_utf8(latest_body())
+        .unwrap()
+        .replace("2458190", "2458191");
+    let transport = MockTransport::new().with_response(LATEST_URI, body.as_bytes());
+
+    t.execute_with(|| {
+        let client = cached_client();
+        assert_eq!(
+            client.latest_with(&transport),
+            Err(ClientError::InvalidSignature)
+        );
+        assert_eq!(client.round_cache().unwrap().latest(), None);
+    })
+}
+
+#[test]
+fn oldest_rounds_are_evicted() {
+    let (offchain, _state) = testing::TestOffchainExt::new();
+    let mut t = TestExternalities::default();
+    t.register_extension(OffchainDbExt::new(offchain));
+
+    t.execute_with(|| {
+        let config = CacheConfig { max_rounds: 2 };
+        let cache = RoundCache::new(b"chain", &config);
+        for round in [5, 3, 7] {
+            cache.insert(&empty_round(round));
+        }
+
+        assert_eq!(cache.get(5), None);
+        assert_eq!(cache.get(3), Some(empty_round(3)));
+        assert_eq!(cache.get(7), Some(empty_round(7)));
+
+        // another chain doesn't see these rounds
+        assert_eq!(RoundCache::new(b"other", &config).get(7), None);
+
+        cache.clear();
+        assert_eq!(cache.get(7), None);
+    })
+}
@@ -0,0 +1,164 @@
+//! Headers sent with the requests, per endpoint, with values from the offchain local storage.
+
+use sp_io::TestExternalities;
+use sp_runtime::offchain::{testing, OffchainDbExt, OffchainWorkerExt, StorageKind};
+
+use super::local_client;
+use crate::{Client, Endpoint, HeaderValue, MockTransport};
+
+const PRIMARY: &str = "http://localhost";
+const RELAY: &str = "http://relay";
+
+fn header(name: &str, value: &str) -> (String, String) {
+    (name.into(), value.into())
+}
+
+fn client_with_headers() -> Client {
+    let mut client = local_client();
+    client.set_relays(&[RELAY]).unwrap();
+    client
+        .set_user_agent("drand-substrate-client/test")
+        .unwrap();
+    client
+        .add_header(
+            RELAY,
+            "Authorization",
+            HeaderValue::Secret(b"drand::relay-token".to_vec().try_into().unwrap()),
+        )
+        .unwrap();
+    client
+        .add_header(
+            RELAY,
+            "X-Tenant",
+            HeaderValue::Plain(b"parachain-2000".to_vec().try_into().unwrap()),
+        )
+        .unwrap();
+    client
+}
+
+#[test]
+fn headers_per_endpoint() {
+    let (offchain, _state) = testing::TestOffchainExt::new();
+    let mut t = TestExternalities::default();
+    t.register_extension(OffchainDbExt::new(offchain.clone()));
+    t.register_extension(OffchainWorkerExt::new(offchain));
+
+    let transport = MockTransport::new()
+        .with_response(&format!("{PRIMARY}/public/latest"), b"{}")
+        .with_response(&format!("{RELAY}/public/latest"), b"{}");
+
+    t.execute_with(|| {
+        sp_io::offchain::local_storage_set(
+            StorageKind::PERSISTENT,
+            b"drand::relay-token",
+            b"Bearer s3cr3t",
+        );
+        let client = client_with_headers();
+        let relay = Client {
+            endpoint: Endpoint::parse(RELAY).unwrap(),
+            ..client.clone()
+        };
+
+        client
+            .make_request_with(&transport, format!("{PRIMARY}/public/latest").into())
+            .unwrap();
+        relay
+            .make_request_with(&transport, format!("{RELAY}/public/latest").into())
+            .unwrap();
+
+        let user_agent = header("User-Agent", "drand-substrate-client/test");
+        assert_eq!(
+            transport.sent_headers(),
+            vec![
+                vec![user_agent.clone()],
+                vec![
+                    user_agent,
+                    header("Authorization", "Bearer s3cr3t"),
+                    header("X-Tenant", "parachain-2000"),
+                ],
+            ]
+        );
+    })
+}
+
+#[test]
+fn missing_secret_is_left_out() {
+    let (offchain, _state) = testing::TestOffchainExt::new();
+    let mut t = TestExternalities::default();
+    t.register_extension(OffchainDbExt::new(offchain.clone()));
+    t.register_extension(OffchainWorkerExt::new(offchain));
+
+    let transport = MockTransport::new().with_response(&format!("{RELAY}/public/latest"), b"{}");
+
+    t.execute_with(|| {
+        let relay = Client {
+            endpoint: Endpoint::parse(RELAY).unwrap(),
+            ..client_with_headers()
+        };
+        relay
+            .make_request_with(&transport, format!("{RELAY}/public/latest").into())
+            .unwrap();
+
+        assert_eq!(
+            transport.sent_headers()[0],
+            vec![
+                header("User-Agent", "drand-substrate-client/test"),
+                header("X-Tenant", "parachain-2000"),
+            ]
+        );
+    })
+}
+
+#[test]
+fn offchain_transport_sends_headers() {
+    let (offchain, state) = testing::TestOffchainExt::new();
+    let mut t = TestExternalities::default();
+    t.register_extension(OffchainWorkerExt::new(offchain));
+
+    t.execute_with(|| {
+        state.write().expect_request(testing::PendingRequest {
+            method: "GET".into(),
+            uri: "http://localhost/public/latest".into(),
+            headers: vec![header("User-Agent", "drand-substrate-client/test")],
+            sent: true,
+            response: Some(b"{}".to_vec()),
+            ..Default::default()
+        });
+
+        let mut client = local_client();
+        client
+            .set_user_agent("drand-substrate-client/test")
+            .unwrap();
+        client
+            .make_request(b"http://localhost/public/latest".to_vec())
+            .unwrap();
+    })
+}
+
+#[test]
+fn header_limits() {
+    let mut client = local_client();
+    for _ in 0..8 {
+        client
+            .add_header(PRIMARY, "X-Header", HeaderValue::Plain(Default::default()))
+            .unwrap();
+    }
+    assert!(client
+        .add_header(PRIMARY, "X-Header", HeaderValue::Plain(Default::default()))
+        .is_err());
+    assert!(client
+        .add_header(
+            &"x".repeat(101),
+            "X-Header",
+            HeaderValue::Plain(Default::default())
+        )
+        .is_err());
+
+    // the user agent is replaced
+    client.set_user_agent("first").unwrap();
+    client.set_user_agent("second").unwrap();
+    assert_eq!(
+        client.resolve_headers(|_| None)[8..],
+        [header("User-Agent", "second")]
+    );
+}
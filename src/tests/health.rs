@@ -0,0 +1,182 @@
+//! `/health` and health-aware endpoint selection.
+
+use std::fs;
+
+use sp_io::TestExternalities;
+use sp_runtime::offchain::{testing, OffchainDbExt, OffchainWorkerExt, Timestamp};
+
+use super::local_client;
+use crate::{
+    Client, ClientError, HeaderValue, Health, HealthRecord, HttpResponse, MockTransport,
+    RetryConfig, RetryState, HEALTH_RECHECK_MS,
+};
+
+const PRIMARY: &str = "http://localhost";
+const RELAY: &str = "http://relay";
+
+fn behind() -> HttpResponse {
+    HttpResponse {
+        code: 500,
+        body: br#"{"current": 2458180, "expected": 2458190}"#.to_vec(),
+        headers: vec![],
+    }
+}
+
+fn synced() -> HttpResponse {
+    HttpResponse::ok(fs::read("./src/tests/testdata/health.json").unwrap())
+}
+
+fn client_with_relay() -> Client {
+    let mut client = local_client();
+    client.set_relays(&[RELAY]).unwrap();
+    client
+}
+
+#[test]
+fn health() {
+    let transport = MockTransport::new().with_response("http://localhost/health", &synced().body);
+    let health = local_client().health_with(&transport).unwrap();
+    assert_eq!(
+        health,
+        Health {
+            current: 2458190,
+            expected: 2458190
+        }
+    );
+    assert!(health.is_synced());
+
+    // the previous round may still be in flight
+    assert!(Health {
+        current: 9,
+        expected: 10
+    }
+    .is_synced());
+
+    let mut transport = MockTransport::new();
+    transport.insert("http://localhost/health", behind());
+    let health = local_client().health_with(&transport).unwrap();
+    assert!(!health.is_synced());
+}
+
+#[test]
+fn select_skips_relays_behind() {
+    let (offchain, state) = testing::TestOffchainExt::new();
+    let mut t = TestExternalities::default();
+    t.register_extension(OffchainDbExt::new(offchain.clone()));
+    t.register_extension(OffchainWorkerExt::new(offchain));
+
+    let mut transport = MockTransport::new();
+    transport.insert(&format!("{PRIMARY}/health"), behind());
+    transport.insert(&format!("{RELAY}/health"), synced());
+
+    t.execute_with(|| {
+        state.write().timestamp = Timestamp::from_unix_millis(1_000_000);
+        let client = client_with_relay();
+
+        let selected = client.select_endpoint_with(&transport).unwrap();
+        assert_eq!(selected.endpoint.as_str(), RELAY);
+        assert_eq!(selected.chain_info, client.chain_info);
+
+        let record = HealthRecord::get(PRIMARY.as_bytes()).unwrap();
+        assert!(!record.is_healthy());
+        assert_eq!(record.checked_at, 1_000_000);
+        assert!(HealthRecord::get(RELAY.as_bytes()).unwrap().is_healthy());
+
+        // recorded checks are reused until they are too old
+        client.select_endpoint_with(&transport).unwrap();
+        assert_eq!(transport.requests().len(), 2);
+
+        state.write().timestamp = Timestamp::from_unix_millis(1_000_000 + HEALTH_RECHECK_MS);
+        client.select_endpoint_with(&transport).unwrap();
+        assert_eq!(transport.requests().len(), 4);
+    })
+}
+
+#[test]
+fn select_fails_without_healthy_endpoint() {
+    let (offchain, _state) = testing::TestOffchainExt::new();
+    let mut t = TestExternalities::default();
+    t.register_extension(OffchainDbExt::new(offchain.clone()));
+    t.register_extension(OffchainWorkerExt::new(offchain));
+
+    // the relay isn't reachable at all
+    let mut transport = MockTransport::new();
+    transport.insert(&format!("{PRIMARY}/health"), behind());
+
+    t.execute_with(|| {
+        assert_eq!(
+            client_with_relay().select_endpoint_with(&transport),
+            Err(ClientError::NoHealthyEndpoint)
+        );
+        assert_eq!(HealthRecord::get(RELAY.as_bytes()).unwrap().health, None);
+    })
+}
+
+#[test]
+fn health_checks_use_the_client_settings() {
+    let (offchain, _state) = testing::TestOffchainExt::new();
+    let mut t = TestExternalities::default();
+    t.register_extension(OffchainDbExt::new(offchain.clone()));
+    t.register_extension(OffchainWorkerExt::new(offchain));
+
+    let mut transport = MockTransport::new();
+    transport.insert(&format!("{PRIMARY}/health"), behind());
+    transport.insert(
+        &format!("{RELAY}/health"),
+        HttpResponse {
+            code: 301,
+            body: vec![],
+            headers: vec![("Location".into(), "/v2/health".into())],
+        },
+    );
+    transport.insert(&format!("{RELAY}/v2/health"), synced());
+
+    t.execute_with(|| {
+        let mut client = client_with_relay();
+        client
+            .add_header(
+                RELAY,
+                "X-Tenant",
+                HeaderValue::Plain(b"parachain-2000".to_vec().try_into().unwrap()),
+            )
+            .unwrap();
+
+        let selected = client.select_endpoint_with(&transport).unwrap();
+        assert_eq!(selected.endpoint.as_str(), RELAY);
+        assert_eq!(
+            transport.requests()[1..],
+            [format!("{RELAY}/health"), format!("{RELAY}/v2/health")]
+        );
+        let tenant = ("X-Tenant".to_string(), "parachain-2000".to_string());
+        assert!(transport.sent_headers()[1..]
+            .iter()
+            .all(|headers| headers.contains(&tenant)));
+    })
+}
+
+#[test]
+fn health_checks_are_not_retried() {
+    let (offchain, _state) = testing::TestOffchainExt::new();
+    let mut t = TestExternalities::default();
+    t.register_extension(OffchainDbExt::new(offchain.clone()));
+    t.register_extension(OffchainWorkerExt::new(offchain));
+
+    let mut transport = MockTransport::new();
+    transport.insert(&format!("{PRIMARY}/health"), behind());
+    transport.insert(&format!("{RELAY}/health"), behind());
+
+    t.execute_with(|| {
+        let mut client = client_with_relay();
+        client.set_retry(Some(RetryConfig::default()));
+
+        assert_eq!(
+            client.select_endpoint_with(&transport),
+            Err(ClientError::NoHealthyEndpoint)
+        );
+        assert_eq!(
+            transport.requests(),
+            vec![format!("{PRIMARY}/health"), format!("{RELAY}/health")]
+        );
+        assert_eq!(RetryState::get(PRIMARY.as_bytes()), RetryState::default());
+    })
+}
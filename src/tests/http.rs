@@ -0,0 +1,140 @@
+//! Redirects, and freshness of cached `latest` responses.
+
+use std::fs;
+
+use super::local_client;
+use crate::{
+    Client, ClientError, Freshness, HeaderValue, HttpResponse, Info, MockTransport, MAX_REDIRECTS,
+};
+
+/// Emission time of round 2458190 (`latest.json`) of the default chain.
+const ROUND_DATE: &str = "Wed, 23 Nov 2022 04:12:00 GMT";
+
+fn redirect(location: &str) -> HttpResponse {
+    HttpResponse {
+        code: 301,
+        body: vec![],
+        headers: vec![],
+    }
+    .with_header("Location", location)
+}
+
+fn latest(age: &str, cache_control: &str) -> HttpResponse {
+    HttpResponse::ok(fs::read("./src/tests/testdata/latest.json").unwrap())
+        .with_header("Date", ROUND_DATE)
+        .with_header("Age", age)
+        .with_header("Cache-Control", cache_control)
+}
+
+#[test]
+fn follows_redirects() {
+    let mut transport = MockTransport::new().with_response(
+        "http://new-relay/info",
+        &fs::read("./src/tests/testdata/chain_info.json").unwrap(),
+    );
+    transport.insert("http://localhost/info", redirect("http://moved/info"));
+    transport.insert("http://moved/info", redirect("//new-relay/info"));
+
+    let info = local_client().info_with(&transport).unwrap();
+    assert_eq!(info, Info::default());
+    assert_eq!(
+        transport.requests(),
+        vec![
+            "http://localhost/info",
+            "http://moved/info",
+            "http://new-relay/info"
+        ]
+    );
+}
+
+#[test]
+fn rejects_scheme_changes_and_loops() {
+    let mut transport = MockTransport::new();
+    transport.insert("https://relay/info", redirect("http://relay/info"));
+    let client = Client::new("https://relay", Info::default()).unwrap();
+    assert_eq!(client.info_with(&transport), Err(ClientError::Http));
+    assert_eq!(transport.requests().len(), 1);
+
+    let mut transport = MockTransport::new();
+    transport.insert("http://localhost/info", redirect("/info"));
+    assert_eq!(local_client().info_with(&transport), Err(ClientError::Http));
+    assert_eq!(transport.requests().len(), MAX_REDIRECTS as usize + 1);
+}
+
+#[test]
+fn headers_stay_on_their_origin() {
+    let mut transport = MockTransport::new().with_response("http://elsewhere/chains", b"[]");
+    transport.insert(
+        "http://localhost/chains",
+        redirect("http://elsewhere/chains"),
+    );
+
+    let mut client = local_client();
+    client
+        .add_header(
+            "http://localhost",
+            "Authorization",
+            HeaderValue::Plain(b"Bearer token".to_vec().try_into().unwrap()),
+        )
+        .unwrap();
+    client.chains_with(&transport).unwrap();
+
+    assert_eq!(
+        transport.sent_headers(),
+        vec![
+            vec![("Authorization".to_string(), "Bearer token".to_string())],
+            vec![]
+        ]
+    );
+}
+
+#[test]
+fn freshness() {
+    let info = Info::default();
+
+    let fresh = Freshness::of(&latest("20", "public, max-age=30"));
+    assert_eq!(
+        fresh,
+        Freshness {
+            age: 20,
+            date: Some(1669176720),
+            max_age: Some(30),
+        }
+    );
+    assert!(fresh.is_fresh(&info, 2458190));
+
+    // two rounds were emitted since it was cached
+    assert!(!Freshness::of(&latest("60", "max-age=300")).is_fresh(&info, 2458190));
+    // past its max-age
+    assert!(!Freshness::of(&latest("20", "max-age=10, s-maxage=5")).is_fresh(&info, 2458190));
+    // not from a cache
+    assert!(Freshness::of(&latest("0", "no-cache")).is_fresh(&info, 2458000));
+}
+
+#[test]
+fn stale_latest_is_fetched_again() {
+    let mut transport = MockTransport::new();
+    transport.insert("http://localhost/public/latest", latest("20", "max-age=30"));
+    assert_eq!(
+        local_client().latest_with(&transport).unwrap().round,
+        2458190
+    );
+    assert_eq!(transport.requests().len(), 1);
+
+    let mut transport = MockTransport::new();
+    transport.insert(
+        "http://localhost/public/latest",
+        latest("60", "max-age=300"),
+    );
+    assert_eq!(
+        local_client().latest_with(&transport),
+        Err(ClientError::StaleResponse)
+    );
+    assert_eq!(
+        transport.sent_headers(),
+        vec![
+            vec![],
+            vec![("Cache-Control".to_string(), "no-cache".to_string())]
+        ]
+    );
+}
@@ -0,0 +1,122 @@
+//! Cross-worker locking around fetches.
+
+use std::fs;
+
+use codec::Encode;
+use sp_io::TestExternalities;
+use sp_runtime::{
+    offchain::{
+        storage_lock::{StorageLock, Time},
+        testing, Duration, OffchainDbExt, OffchainWorkerExt, Timestamp,
+    },
+    traits::BlockNumberProvider,
+};
+
+use super::local_client;
+use crate::{lock_key, CacheConfig, Client, Info, LockConfig, MockTransport, SystemBlockNumber};
+
+const ROUND_URI: &str = "http://localhost/public/2458190";
+
+fn locked_client(lock: LockConfig) -> Client {
+    let mut client = local_client();
+    client.set_cache(Some(CacheConfig::default()));
+    client.set_lock(Some(lock));
+    client
+}
+
+fn transport() -> MockTransport {
+    MockTransport::new().with_response(
+        ROUND_URI,
+        &fs::read("./src/tests/testdata/latest.json").unwrap(),
+    )
+}
+
+fn round_lock_key() -> Vec<u8> {
+    lock_key(&Info::default().hash, b"round::2458190")
+}
+
+#[test]
+fn lock_is_released_after_fetch() {
+    let (offchain, _state) = testing::TestOffchainExt::new();
+    let mut t = TestExternalities::default();
+    t.register_extension(OffchainDbExt::new(offchain.clone()));
+    t.register_extension(OffchainWorkerExt::new(offchain));
+    let transport = transport();
+
+    t.execute_with(|| {
+        let client = locked_client(LockConfig::default());
+        assert!(client.round_with(&transport, 2458190).is_ok());
+
+        let key = round_lock_key();
+        let mut lock = StorageLock::<Time>::with_deadline(&key, Duration::from_millis(1_000));
+        assert!(lock.try_lock().is_ok());
+    })
+}
+
+#[test]
+fn waits_for_lock_held_by_another_worker() {
+    let (offchain, state) = testing::TestOffchainExt::new();
+    let mut t = TestExternalities::default();
+    t.register_extension(OffchainDbExt::new(offchain.clone()));
+    t.register_extension(OffchainWorkerExt::new(offchain));
+    let transport = transport();
+
+    t.execute_with(|| {
+        state.write().timestamp = Timestamp::from_unix_millis(10_000);
+
+        // a worker took the lock and was killed before releasing it
+        let key = round_lock_key();
+        let mut lock = StorageLock::<Time>::with_deadline(&key, Duration::from_millis(3_000));
+        lock.lock().forget();
+
+        let client = locked_client(LockConfig::default());
+        assert!(client.round_with(&transport, 2458190).is_ok());
+        assert!(state.read().timestamp.unix_millis() >= 13_000);
+        assert_eq!(transport.requests().len(), 1);
+    })
+}
+
+#[test]
+fn cached_round_does_not_wait_for_lock() {
+    let (offchain, state) = testing::TestOffchainExt::new();
+    let mut t = TestExternalities::default();
+    t.register_extension(OffchainDbExt::new(offchain.clone()));
+    t.register_extension(OffchainWorkerExt::new(offchain));
+    let transport = transport();
+
+    t.execute_with(|| {
+        state.write().timestamp = Timestamp::from_unix_millis(10_000);
+        let client = locked_client(LockConfig::default());
+        let round = client.round_with(&transport, 2458190).unwrap();
+
+        let key = round_lock_key();
+        let mut lock = StorageLock::<Time>::with_deadline(&key, Duration::from_millis(3_000));
+        let _guard = lock.lock();
+
+        assert_eq!(client.round_with(&transport, 2458190), Ok(round));
+        assert_eq!(state.read().timestamp.unix_millis(), 10_000);
+        assert_eq!(transport.requests().len(), 1);
+    })
+}
+
+#[test]
+fn block_and_time_lock() {
+    let (offchain, _state) = testing::TestOffchainExt::new();
+    let mut t = TestExternalities::default();
+    t.register_extension(OffchainDbExt::new(offchain.clone()));
+    t.register_extension(OffchainWorkerExt::new(offchain));
+    let transport = transport();
+
+    t.execute_with(|| {
+        let mut key = sp_io::hashing::twox_128(b"System").to_vec();
+        key.extend(sp_io::hashing::twox_128(b"Number"));
+        sp_io::storage::set(&key, &42u32.encode());
+        assert_eq!(SystemBlockNumber::current_block_number(), 42);
+
+        let client = locked_client(LockConfig::BlockAndTime {
+            expiration_blocks: 2,
+            expiration_ms: 5_000,
+        });
+        assert!(client.round_with(&transport, 2458190).is_ok());
+    })
+}
@@ -0,0 +1,65 @@
+//! Decoding `Client`s stored by earlier versions.
+
+use codec::Encode;
+
+use crate::{Client, ClientError, ClientV0, Info, InfoV0, MAINNET_ENDPOINT};
+
+fn mainnet_v0() -> ClientV0 {
+    let info = Info::default();
+    ClientV0 {
+        endpoint: b"https://drand.cloudflare.com/"
+            .to_vec()
+            .try_into()
+            .unwrap(),
+        chain_info: Some(InfoV0 {
+            public_key: info.public_key.to_vec().try_into().unwrap(),
+            period: info.period,
+            genesis_time: info.genesis_time,
+            hash: info.hash,
+            group_hash: info.group_hash,
+        }),
+    }
+}
+
+#[test]
+fn decode_version_0() {
+    let encoded = mainnet_v0().encode();
+    let client = Client::decode_versioned(&encoded).unwrap();
+    assert_eq!(client, Client::default());
+    assert_eq!(client.endpoint.as_str(), MAINNET_ENDPOINT);
+    assert!(client.chain_info().unwrap().has_valid_hash());
+
+    let without_chain = ClientV0 {
+        chain_info: None,
+        ..mainnet_v0()
+    };
+    assert_eq!(
+        Client::decode_versioned(&without_chain.encode())
+            .unwrap()
+            .chain_info,
+        None
+    );
+}
+
+#[test]
+fn decode_current_version() {
+    let mut client = Client::default();
+    client.set_relays(&["https://api.drand.sh"]).unwrap();
+    assert_eq!(Client::decode_versioned(&client.encode()), Ok(client));
+}
+
+#[test]
+fn decode_invalid() {
+    let mut encoded = mainnet_v0().encode();
+    encoded.push(0);
+    assert_eq!(Client::decode_versioned(&encoded), Err(ClientError::Misc));
+
+    let not_a_url = ClientV0 {
+        endpoint: b"drand".to_vec().try_into().unwrap(),
+        ..mainnet_v0()
+    };
+    assert_eq!(
+        Client::decode_versioned(&not_a_url.encode()),
+        Err(ClientError::InvalidEndpoint)
+    );
+}
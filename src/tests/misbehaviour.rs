@@ -0,0 +1,222 @@
+//! Cross-relay checks, and misbehaviour reports.
+
+use codec::{Decode, Encode};
+use drand_verify::derive_randomness;
+
+use crate::{
+    reported_by_quorum, Client, Info, MisbehaviourReport, MockTransport, Round, Scheme,
+    VerificationFailure, MAX_REPORT_PAYLOAD,
+};
+
+use super::{
+    bootstrap::info_json,
+    tlock::{round_json, sign, test_chain},
+};
+
+const PRIMARY: &str = "http://localhost";
+const RELAY: &str = "http://relay";
+
+fn client(info: &Info) -> Client {
+    let mut client = Client::new(PRIMARY, info.clone()).unwrap();
+    client.set_relays(&[RELAY]).unwrap();
+    client
+}
+
+/// The report of the relay, serving `body` for round 1000 of `info`.
+fn relay_report(info: &Info, body: &[u8]) -> MisbehaviourReport {
+    let transport = MockTransport::new()
+        .with_response(
+            &format!("{PRIMARY}/public/1000"),
+            &round_json(&sign(info, 1000)),
+        )
+        .with_response(&format!("{RELAY}/public/1000"), body);
+
+    let check = client(info).cross_check_with(&transport, 1000).unwrap();
+    assert_eq!(check.round, Some(sign(info, 1000)));
+    assert_eq!(check.reports.len(), 1);
+
+    let report = check.reports[0].clone();
+    assert_eq!(report.endpoint.as_str(), RELAY);
+    assert_eq!(report.round, 1000);
+    assert_eq!(report.payload.as_slice(), body);
+    assert!(report.check(info));
+    report
+}
+
+#[test]
+fn honest_relays() {
+    let info = test_chain(Scheme::BlsUnchainedG1Rfc9380);
+    let body = round_json(&sign(&info, 1000));
+    let transport = MockTransport::new()
+        .with_response(&format!("{PRIMARY}/public/1000"), &body)
+        .with_response(&format!("{RELAY}/public/1000"), &body);
+
+    let check = client(&info).cross_check_with(&transport, 1000).unwrap();
+    assert_eq!(check.round, Some(sign(&info, 1000)));
+    assert!(check.reports.is_empty());
+
+    // unreachable relays aren't reported
+    let transport = MockTransport::new().with_response(&format!("{PRIMARY}/public/1000"), &body);
+    let check = client(&info).cross_check_with(&transport, 1000).unwrap();
+    assert!(check.reports.is_empty());
+}
+
+#[test]
+fn reports_invalid_rounds() {
+    let info = test_chain(Scheme::BlsUnchainedG1Rfc9380);
+
+    let forged = Round {
+        round: 1000,
+        ..sign(&info, 1001)
+    };
+    let report = relay_report(&info, &round_json(&forged));
+    assert_eq!(report.failure, VerificationFailure::InvalidSignature);
+
+    let wrong_randomness = Round {
+        randomness: [0u8; 32].to_vec().try_into().unwrap(),
+        ..sign(&info, 1000)
+    };
+    let report = relay_report(&info, &round_json(&wrong_randomness));
+    assert_eq!(report.failure, VerificationFailure::InvalidRandomness);
+
+    let report = relay_report(&info, &round_json(&sign(&info, 999)));
+    assert_eq!(
+        report.failure,
+        VerificationFailure::WrongRound { served: 999 }
+    );
+
+    let not_a_point = [0xffu8; 48];
+    let undecodable = Round {
+        round: 1000,
+        randomness: derive_randomness(&not_a_point).to_vec().try_into().unwrap(),
+        previous_signature: Default::default(),
+        signature: not_a_point.to_vec().try_into().unwrap(),
+    };
+    let report = relay_report(&info, &round_json(&undecodable));
+    assert_eq!(report.failure, VerificationFailure::InvalidSignature);
+
+    let report = relay_report(
+        &info,
+        br#"{"round": 1000, "randomness": "", "signature": "not hex"}"#,
+    );
+    assert_eq!(report.failure, VerificationFailure::InvalidRandomness);
+
+    let report = relay_report(&info, b"<html>");
+    assert_eq!(report.failure, VerificationFailure::Malformed);
+
+    // reports are SCALE-encodable, for a pallet to store them
+    assert_eq!(
+        MisbehaviourReport::decode(&mut &report.encode()[..]).unwrap(),
+        report
+    );
+}
+
+#[test]
+fn reports_rounds_of_another_chain() {
+    let info = test_chain(Scheme::BlsUnchainedG1Rfc9380);
+    let mut other = test_chain(Scheme::PedersenBlsUnchained);
+    other.hash = other.compute_hash().to_vec().try_into().unwrap();
+
+    let transport = MockTransport::new()
+        .with_response(
+            &format!("{PRIMARY}/public/1000"),
+            &round_json(&sign(&info, 1000)),
+        )
+        .with_response(
+            &format!("{RELAY}/public/1000"),
+            &round_json(&sign(&other, 1000)),
+        )
+        .with_response(&format!("{RELAY}/info"), &info_json(&other));
+
+    let check = client(&info).cross_check_with(&transport, 1000).unwrap();
+    let report = &check.reports[0];
+    assert_eq!(
+        report.failure,
+        VerificationFailure::OtherChain {
+            chain_hash: other.hash.clone()
+        }
+    );
+    assert!(report.check(&info));
+}
+
+#[test]
+fn reports_are_checked() {
+    let info = test_chain(Scheme::BlsUnchainedG1Rfc9380);
+    let forged = Round {
+        round: 1000,
+        ..sign(&info, 1001)
+    };
+    let report = relay_report(&info, &round_json(&forged));
+
+    // a valid round isn't evidence
+    let framed = MisbehaviourReport {
+        payload: round_json(&sign(&info, 1000)).try_into().unwrap(),
+        ..report.clone()
+    };
+    assert!(!framed.check(&info));
+
+    // the payload must fail the way it's reported
+    let misreported = MisbehaviourReport {
+        failure: VerificationFailure::Malformed,
+        ..report.clone()
+    };
+    assert!(!misreported.check(&info));
+
+    // against the chain it was requested from
+    let mut other = test_chain(Scheme::PedersenBlsUnchained);
+    other.hash = other.compute_hash().to_vec().try_into().unwrap();
+    assert!(!report.check(&other));
+}
+
+#[test]
+fn large_responses_are_not_reported() {
+    let info = test_chain(Scheme::BlsUnchainedG1Rfc9380);
+    let padded = |round: &Round| {
+        let mut body = round_json(round);
+        body.extend(vec![b' '; MAX_REPORT_PAYLOAD as usize]);
+        body
+    };
+    let forged = Round {
+        round: 1000,
+        ..sign(&info, 1001)
+    };
+    let transport = MockTransport::new()
+        .with_response(
+            &format!("{PRIMARY}/public/1000"),
+            &padded(&sign(&info, 1000)),
+        )
+        .with_response(&format!("{RELAY}/public/1000"), &padded(&forged));
+
+    // still verified when honest, but can't be carried by a report when not
+    let check = client(&info).cross_check_with(&transport, 1000).unwrap();
+    assert_eq!(check.round, Some(sign(&info, 1000)));
+    assert!(check.reports.is_empty());
+}
+
+#[test]
+fn acting_on_reports_takes_a_quorum() {
+    let info = test_chain(Scheme::BlsUnchainedG1Rfc9380);
+    let forged = Round {
+        round: 1000,
+        ..sign(&info, 1001)
+    };
+    let report = relay_report(&info, &round_json(&forged));
+    let framed = MisbehaviourReport {
+        payload: round_json(&sign(&info, 1000)).try_into().unwrap(),
+        ..report.clone()
+    };
+
+    // a reporter counts once, and only with reports that check
+    let reports = [
+        ("alice", report.clone()),
+        ("alice", report.clone()),
+        ("bob", framed),
+    ];
+    assert!(reported_by_quorum(&reports, &info, 2).is_empty());
+
+    let reports = [("alice", report.clone()), ("bob", report.clone())];
+    assert_eq!(
+        reported_by_quorum(&reports, &info, 2),
+        vec![report.endpoint]
+    );
+}
@@ -0,0 +1,100 @@
+//! drand randomness mixed with a local source.
+
+use std::cell::RefCell;
+
+use frame_support::traits::Randomness;
+
+use crate::{mix, DrandRoundProvider, MixedRandomness, Round};
+
+thread_local! {
+    static ROUND: RefCell<Option<Round>> = const { RefCell::new(None) };
+}
+
+struct TestRound;
+
+impl DrandRoundProvider for TestRound {
+    fn round() -> Option<Round> {
+        ROUND.with(|round| round.borrow().clone())
+    }
+}
+
+/// Stands in for BABE: the same output for every subject, at block 42.
+struct TestLocal;
+
+impl Randomness<[u8; 32], u64> for TestLocal {
+    fn random(_subject: &[u8]) -> ([u8; 32], u64) {
+        ([3u8; 32], 42)
+    }
+}
+
+type Mixed = MixedRandomness<TestRound, TestLocal>;
+
+fn set_round(randomness: u8) {
+    let round = Round {
+        round: 1000,
+        randomness: vec![randomness; 32].try_into().unwrap(),
+        previous_signature: Default::default(),
+        signature: Default::default(),
+    };
+    ROUND.with(|r| *r.borrow_mut() = Some(round));
+}
+
+#[test]
+fn mixes_both_sources() {
+    set_round(1);
+    let (output, block_number) = Mixed::random(b"lottery");
+    assert_eq!(block_number, 42);
+    assert_ne!(output, [3u8; 32]);
+    assert_ne!(output, [1u8; 32]);
+    assert_eq!(
+        output,
+        mix(b"lottery", &TestRound::round().unwrap(), &[3u8; 32])
+    );
+    assert_eq!(Mixed::try_random(b"lottery"), Some((output, 42u64)));
+
+    // each source changes the output
+    assert_ne!(Mixed::random(b"raffle").0, output);
+    set_round(2);
+    assert_ne!(Mixed::random(b"lottery").0, output);
+
+    let (seed, _) = <Mixed as Randomness<[u8; 32], u64>>::random_seed();
+    assert_eq!(seed, Mixed::random(&[]).0);
+}
+
+#[test]
+fn unusable_without_round() {
+    ROUND.with(|r| *r.borrow_mut() = None);
+    assert_eq!(Mixed::try_random::<[u8; 32], u64>(b"lottery"), None);
+
+    // determined since block zero, i.e. before any commitment it could settle
+    let (output, block_number) = Mixed::random(b"lottery");
+    assert_eq!(block_number, 0);
+    assert_ne!(output, [3u8; 32]);
+
+    let empty_round = Round {
+        round: 0,
+        randomness: Default::default(),
+        previous_signature: Default::default(),
+        signature: Default::default(),
+    };
+    assert_eq!(output, mix(b"lottery", &empty_round, &[3u8; 32]));
+}
+
+#[test]
+fn decodes_into_smaller_outputs() {
+    struct SmallLocal;
+    impl Randomness<u64, u64> for SmallLocal {
+        fn random(_subject: &[u8]) -> (u64, u64) {
+            (7, 1)
+        }
+    }
+
+    set_round(1);
+    let (output, _) = MixedRandomness::<TestRound, SmallLocal>::random(b"lottery");
+    let mixed = mix(
+        b"lottery",
+        &TestRound::round().unwrap(),
+        &7u64.to_le_bytes(),
+    );
+    assert_eq!(output.to_le_bytes(), mixed[..8]);
+}
@@ -9,6 +9,39 @@ use drand_verify::derive_randomness;
 
 use crate::{util::hex_to_vec_u8, ChainsRaw, Client, Info, InfoRaw, RoundRaw};
 
+mod api;
+#[cfg(feature = "async")]
+mod async_client;
+mod backfill;
+mod bootstrap;
+mod builder;
+mod cache;
+mod derive;
+mod endpoint;
+mod headers;
+mod health;
+mod http;
+mod lock;
+mod migration;
+mod misbehaviour;
+mod mix;
+mod multi;
+#[cfg(feature = "native-http")]
+mod native;
+mod networks;
+mod requests;
+mod retry;
+mod rng;
+mod stats;
+mod tle;
+mod tlock;
+mod transport;
+
+/// Client of the mainnet chain, served at `http://localhost`.
+pub(super) fn local_client() -> Client {
+    Client::new("http://localhost", Info::default()).unwrap()
+}
+
 fn get_info_string() -> Vec<u8> {
     let filename = "./src/tests/testdata/chain_info.json";
     let file = File::open(filename).unwrap();
@@ -33,7 +66,7 @@ fn get_chains() {
     let expected_response_chains = chains_string.as_bytes();
 
     t.execute_with(|| {
-        let client = Client::default();
+        let client = local_client();
         state.write().expect_request(testing::PendingRequest {
             method: "GET".into(),
             uri: CHAINS_URI.into(),
@@ -56,7 +89,7 @@ fn get_info() {
     let expected_response_info = get_info_string();
 
     t.execute_with(|| {
-        let client = Client::default();
+        let client = local_client();
         state.write().expect_request(testing::PendingRequest {
             method: "GET".into(),
             uri: INFO_URI.into(),
@@ -84,7 +117,7 @@ fn get_round() {
     let expected_response = round_string.as_bytes();
 
     t.execute_with(|| {
-        let client = Client::default();
+        let client = local_client();
         state.write().expect_request(testing::PendingRequest {
             method: "GET".into(),
             uri: "http://localhost/public/2458190".into(),
@@ -112,7 +145,7 @@ fn get_latest() {
     let expected_response = round_string.as_bytes();
 
     t.execute_with(|| {
-        let client = Client::default();
+        let client = local_client();
         state.write().expect_request(testing::PendingRequest {
             method: "GET".into(),
             uri: LATEST_URI.into(),
@@ -141,7 +174,7 @@ fn get_latest_err() {
     expected_response[0] += 1;
 
     t.execute_with(|| {
-        let client = Client::default();
+        let client = local_client();
         state.write().expect_request(testing::PendingRequest {
             method: "GET".into(),
             uri: LATEST_URI.into(),
@@ -176,7 +209,7 @@ pub fn verify_randomness() {
     let expected_response = latest_round_serialized.as_bytes();
 
     t.execute_with(|| {
-        let client = Client::default();
+        let client = local_client();
         state.write().expect_request(testing::PendingRequest {
             method: "GET".into(),
             uri: LATEST_URI.into(),
@@ -186,8 +219,10 @@ pub fn verify_randomness() {
             ..Default::default()
         });
         let round = client.latest().unwrap();
-        let randomness = Client::verify_randomness(&round, &chain_info.public_key);
+        let public_key = chain_info.public_key.to_vec().try_into().unwrap();
+        let randomness = Client::verify_randomness(&round, &public_key);
         assert!(randomness.is_ok());
+        assert_eq!(Client::verify_round(&round, &chain_info), randomness);
     })
 }
 
@@ -0,0 +1,169 @@
+//! Randomness combined from several chains.
+
+use sp_io::TestExternalities;
+use sp_runtime::offchain::{testing, OffchainWorkerExt};
+
+use crate::{
+    util::vec_u8_to_hex, Client, ClientError, Info, MockTransport, MultiClient, OffchainTransport,
+    Round, Scheme,
+};
+
+use super::tlock::{round_json, sign, test_chain};
+
+const BASE_URL: &str = "http://localhost";
+
+fn chains() -> (Info, Info) {
+    let default = test_chain(Scheme::PedersenBlsUnchained);
+    let mut quick = test_chain(Scheme::BlsUnchainedG1Rfc9380);
+    quick.hash = vec![2u8; 32].try_into().unwrap();
+    quick.period = 3;
+    quick.genesis_time = default.genesis_time + 30;
+    (default, quick)
+}
+
+fn serve(transport: &mut MockTransport, info: &Info, round: &Round) {
+    let url = format!(
+        "{BASE_URL}/{}/public/{}",
+        vec_u8_to_hex(&info.hash),
+        round.round
+    );
+    transport.insert(&url, crate::HttpResponse::ok(round_json(round)));
+}
+
+fn multi_client(default: &Info, quick: &Info) -> MultiClient {
+    MultiClient::new(vec![
+        Client::for_chain(BASE_URL, default.clone()).unwrap(),
+        Client::for_chain(&format!("{BASE_URL}/"), quick.clone()).unwrap(),
+    ])
+    .unwrap()
+}
+
+#[test]
+fn combines_rounds_emitted_at_the_same_time() {
+    let (default, quick) = chains();
+    let timestamp = default.genesis_time + 95;
+    assert_eq!(default.round_at(timestamp), 4);
+    assert_eq!(quick.round_at(timestamp), 22);
+
+    let mut transport = MockTransport::new();
+    serve(&mut transport, &default, &sign(&default, 4));
+    serve(&mut transport, &quick, &sign(&quick, 22));
+
+    let multi = multi_client(&default, &quick);
+    let combined = multi.combined_at_with(&transport, timestamp).unwrap();
+
+    assert_eq!(combined.rounds.len(), 2);
+    assert_eq!(combined.rounds[0].chain_hash, default.hash);
+    assert_eq!(combined.rounds[0].round.round, 4);
+    assert_eq!(combined.rounds[1].round.round, 22);
+    assert_ne!(
+        combined.randomness.as_slice(),
+        &combined.rounds[0].round.randomness[..]
+    );
+
+    assert_eq!(multi.verify(&combined, timestamp), Ok(()));
+
+    let mut forged = combined.clone();
+    forged.randomness[0] ^= 1;
+    assert_eq!(
+        forged.verify(&[default.clone(), quick.clone()], timestamp),
+        Err(ClientError::RandomnessVerificationError)
+    );
+    assert_eq!(
+        combined.verify(&[quick, default], timestamp),
+        Err(ClientError::RandomnessVerificationError)
+    );
+}
+
+#[test]
+fn rounds_must_be_emitted_at_the_timestamp() {
+    let (default, quick) = chains();
+    let timestamp = default.genesis_time + 95;
+
+    // valid rounds, but of another time: a combiner could pick whichever suits them
+    let mut transport = MockTransport::new();
+    serve(&mut transport, &default, &sign(&default, 4));
+    serve(&mut transport, &quick, &sign(&quick, 21));
+    serve(&mut transport, &quick, &sign(&quick, 22));
+
+    let multi = multi_client(&default, &quick);
+    let earlier = multi.combined_at_with(&transport, timestamp - 3).unwrap();
+    assert_eq!(earlier.rounds[1].round.round, 21);
+    assert_eq!(
+        multi.verify(&earlier, timestamp),
+        Err(ClientError::RandomnessVerificationError)
+    );
+}
+
+#[test]
+fn every_chain_is_required() {
+    let (default, quick) = chains();
+    let timestamp = default.genesis_time + 95;
+
+    // only the default chain is served
+    let mut transport = MockTransport::new();
+    serve(&mut transport, &default, &sign(&default, 4));
+
+    let multi = multi_client(&default, &quick);
+    assert_eq!(
+        multi.combined_at_with(&transport, timestamp),
+        Err(ClientError::Http)
+    );
+
+    // a combination leaving a chain out doesn't verify
+    let default_only =
+        MultiClient::new(vec![Client::for_chain(BASE_URL, default.clone()).unwrap()]).unwrap();
+    let combined = default_only
+        .combined_at_with(&transport, timestamp)
+        .unwrap();
+    assert_eq!(default_only.verify(&combined, timestamp), Ok(()));
+    assert_eq!(
+        multi.verify(&combined, timestamp),
+        Err(ClientError::RandomnessVerificationError)
+    );
+
+    // a round that doesn't verify fails the combination
+    let mut wrong_round = sign(&default, 4);
+    wrong_round.round = 5;
+    let mut transport = MockTransport::new();
+    serve(&mut transport, &default, &wrong_round);
+    assert_eq!(
+        default_only.combined_at_with(&transport, default.genesis_time + 125),
+        Err(ClientError::InvalidSignature)
+    );
+
+    assert_eq!(
+        MultiClient::new(vec![])
+            .unwrap()
+            .combined_at_with(&transport, timestamp),
+        Err(ClientError::ChainNotConfigured)
+    );
+}
+
+#[test]
+fn each_client_uses_its_transport() {
+    let (default, quick) = chains();
+    let timestamp = default.genesis_time + 95;
+    let (offchain, state) = testing::TestOffchainExt::new();
+    let mut t = TestExternalities::default();
+    t.register_extension(OffchainWorkerExt::new(offchain));
+
+    for (info, round) in [(&default, 4), (&quick, 22)] {
+        state.write().expect_request(testing::PendingRequest {
+            method: "GET".into(),
+            uri: format!("{BASE_URL}/{}/public/{round}", vec_u8_to_hex(&info.hash)),
+            sent: true,
+            response: Some(round_json(&sign(info, round))),
+            ..Default::default()
+        });
+    }
+
+    t.execute_with(|| {
+        let mut multi = multi_client(&default, &quick);
+        multi.clients[1].set_transport(OffchainTransport::new().with_max_body_size(16));
+        assert_eq!(
+            multi.combined_at(timestamp),
+            Err(ClientError::ResponseTooLarge)
+        );
+    })
+}
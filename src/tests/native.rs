@@ -0,0 +1,108 @@
+//! `NativeTransport` against a local HTTP stand-in for a drand relay, serving the fixtures in
+//! `src/tests/testdata`.
+
+use std::{fs, thread};
+
+use tiny_http::{Header, Response, Server};
+
+use crate::{Client, ClientError, Info, NativeTransport, Transport};
+
+/// Starts a relay stand-in on an ephemeral port and returns its base URL.
+fn serve_testdata() -> String {
+    let server = Server::http("127.0.0.1:0").unwrap();
+    let port = server.server_addr().to_ip().unwrap().port();
+
+    thread::spawn(move || {
+        for request in server.incoming_requests() {
+            let fixture = match request.url() {
+                "/chains" => Some("chains.json"),
+                "/info" => Some("chain_info.json"),
+                "/public/latest" | "/public/2458190" => Some("latest.json"),
+                _ => None,
+            };
+            let response = match fixture {
+                Some(name) => {
+                    Response::from_data(fs::read(format!("./src/tests/testdata/{name}")).unwrap())
+                }
+                None if request.url() == "/moved/info" => Response::from_string("")
+                    .with_status_code(302)
+                    .with_header(Header::from_bytes("Location", "/info").unwrap()),
+                None => Response::from_string("not found").with_status_code(404),
+            };
+            let _ = request.respond(response);
+        }
+    });
+
+    format!("http://127.0.0.1:{port}")
+}
+
+#[test]
+fn native_get_info_and_chains() {
+    let client = Client::new(&serve_testdata(), Info::default()).unwrap();
+    let transport = NativeTransport::new();
+
+    let info = client.info_with(&transport).unwrap();
+    assert_eq!(info.hash, Info::default().hash);
+    assert!(client.chains_with(&transport).is_ok());
+}
+
+#[test]
+fn native_get_round_verified() {
+    let client = Client::new(&serve_testdata(), Info::default()).unwrap();
+    let transport = NativeTransport::new();
+
+    assert_eq!(client.latest_with(&transport).unwrap().round, 2458190);
+    assert_eq!(
+        client.round_with(&transport, 2458190).unwrap().round,
+        2458190
+    );
+}
+
+#[test]
+fn native_not_found() {
+    let client = Client::new(&serve_testdata(), Info::default()).unwrap();
+
+    assert_eq!(
+        client.round_with(&NativeTransport::new(), 1),
+        Err(ClientError::Http)
+    );
+}
+
+#[test]
+fn native_connection_refused() {
+    // nothing listens on port 1
+    let client = Client::new("http://127.0.0.1:1", Info::default()).unwrap();
+
+    assert_eq!(
+        client.latest_with(&NativeTransport::new()),
+        Err(ClientError::Http)
+    );
+}
+
+#[test]
+fn native_response_too_large() {
+    let client = Client::new(&serve_testdata(), Info::default()).unwrap();
+    let transport = NativeTransport::new().with_max_body_size(100);
+
+    assert_eq!(
+        client.latest_with(&transport),
+        Err(ClientError::ResponseTooLarge)
+    );
+}
+
+#[test]
+fn native_returns_redirects() {
+    let base = serve_testdata();
+    let transport = NativeTransport::new();
+
+    // left to the client, which only follows redirects to the same scheme
+    let response = transport.get(&format!("{base}/moved/info")).unwrap();
+    assert_eq!(response.code, 302);
+    assert_eq!(response.header("location"), Some("/info"));
+
+    let client = Client::new(&format!("{base}/moved"), Info::default()).unwrap();
+    assert_eq!(
+        client.info_with(&transport).unwrap().hash,
+        Info::default().hash
+    );
+}
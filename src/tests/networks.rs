@@ -0,0 +1,99 @@
+//! Registry of well-known networks.
+
+use crate::{
+    util::hex_to_vec_u8, Client, ClientBuilder, ClientError, Info, MockTransport, Network, Round,
+    Scheme, EVMNET, MAINNET, NETWORKS, QUICKNET, TESTNET, TESTNET_QUICKNET,
+};
+
+/// The info of `network` hashes to its chain hash.
+fn assert_chain_hash(network: &Network) {
+    let info = network.info();
+    assert_eq!(
+        info.compute_hash().to_vec(),
+        hex_to_vec_u8(network.chain_hash).unwrap()
+    );
+    assert!(info.has_valid_hash());
+}
+
+#[test]
+fn mainnet_chain_hash() {
+    assert_chain_hash(&MAINNET);
+    assert_eq!(MAINNET.info(), Info::default());
+}
+
+#[test]
+fn quicknet_chain_hash() {
+    assert_chain_hash(&QUICKNET);
+}
+
+#[test]
+fn testnet_quicknet_chain_hash() {
+    assert_chain_hash(&TESTNET_QUICKNET);
+}
+
+#[test]
+fn testnet_chain_hash() {
+    assert_chain_hash(&TESTNET);
+}
+
+#[test]
+fn evmnet_chain_hash() {
+    assert_chain_hash(&EVMNET);
+    assert_eq!(EVMNET.info().public_key.len(), 128);
+
+    // recognized, but its rounds can't be verified
+    let round = Round {
+        round: 1,
+        randomness: Default::default(),
+        previous_signature: Default::default(),
+        signature: vec![0; 64].try_into().unwrap(),
+    };
+    assert_eq!(
+        Client::verify_round(&round, &EVMNET.info()),
+        Err(ClientError::RandomnessVerificationError)
+    );
+}
+
+#[test]
+fn select_networks() {
+    assert_eq!(Network::by_name("quicknet"), Some(&QUICKNET));
+    assert_eq!(Network::by_name("quicknet-t"), Some(&TESTNET_QUICKNET));
+    assert_eq!(Network::by_name("fastnet"), None);
+    assert_eq!(
+        Network::by_chain_hash(&QUICKNET.info().hash),
+        Some(&QUICKNET)
+    );
+    assert_eq!(Network::by_chain_hash(&[0xab; 32]), None);
+
+    for network in &NETWORKS {
+        assert_eq!(Network::by_name(network.name), Some(network));
+        assert_eq!(network.endpoints().len(), network.relays.len());
+    }
+    assert_eq!(MAINNET.endpoints()[0], "https://drand.cloudflare.com");
+    assert_eq!(TESTNET.endpoints()[0], "https://pl-us.testnet.drand.sh");
+    assert_eq!(
+        TESTNET_QUICKNET.endpoints()[0],
+        format!(
+            "https://pl-us.testnet.drand.sh/{}",
+            TESTNET_QUICKNET.chain_hash
+        )
+    );
+}
+
+#[test]
+fn build_from_registry() {
+    let client = ClientBuilder::network(&TESTNET_QUICKNET).build().unwrap();
+    assert_eq!(client.chain_info, Some(TESTNET_QUICKNET.info()));
+    assert_eq!(client.relays.len(), 1);
+
+    // a known chain hash needs no request
+    let transport = MockTransport::new();
+    let client = ClientBuilder::new()
+        .with_endpoint("http://localhost")
+        .with_chain_hash(&QUICKNET.info().hash)
+        .with_scheme(Scheme::BlsUnchainedG1Rfc9380)
+        .build_with(&transport)
+        .unwrap();
+    assert_eq!(client.chain_info, Some(QUICKNET.info()));
+    assert!(transport.requests().is_empty());
+}
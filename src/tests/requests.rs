@@ -0,0 +1,177 @@
+//! Requests of randomness from future rounds.
+
+use std::{cell::RefCell, collections::BTreeMap};
+
+use crate::{
+    Client, ClientError, HttpResponse, MockTransport, OnRandomness, RandomnessRequest,
+    RequestError, RequestStore, Requests, Round, Scheme, REQUEST_CONTEXT,
+};
+
+use super::tlock::{round_json, sign, test_chain};
+
+#[derive(Debug, PartialEq)]
+enum Event {
+    Fulfilled(u64, u64, [u8; 32]),
+    Timeout(u64),
+    Cancelled(u64),
+}
+
+thread_local! {
+    static STORE: RefCell<BTreeMap<u64, RandomnessRequest>> = const { RefCell::new(BTreeMap::new()) };
+    static EVENTS: RefCell<Vec<Event>> = const { RefCell::new(Vec::new()) };
+}
+
+struct TestStore;
+
+impl RequestStore for TestStore {
+    fn insert(request: RandomnessRequest) -> Result<(), RequestError> {
+        STORE.with(|store| {
+            let mut store = store.borrow_mut();
+            if store.len() >= 3 {
+                return Err(RequestError::StoreFull);
+            }
+            store.insert(request.id, request);
+            Ok(())
+        })
+    }
+
+    fn get(id: u64) -> Option<RandomnessRequest> {
+        STORE.with(|store| store.borrow().get(&id).cloned())
+    }
+
+    fn remove(id: u64) -> Option<RandomnessRequest> {
+        STORE.with(|store| store.borrow_mut().remove(&id))
+    }
+
+    fn pending() -> Vec<RandomnessRequest> {
+        STORE.with(|store| store.borrow().values().cloned().collect())
+    }
+}
+
+struct TestHook;
+
+impl OnRandomness for TestHook {
+    fn on_fulfilled(id: u64, round: &Round, randomness: [u8; 32]) {
+        EVENTS.with(|events| {
+            events
+                .borrow_mut()
+                .push(Event::Fulfilled(id, round.round, randomness))
+        });
+    }
+
+    fn on_timeout(id: u64) {
+        EVENTS.with(|events| events.borrow_mut().push(Event::Timeout(id)));
+    }
+
+    fn on_cancelled(id: u64) {
+        EVENTS.with(|events| events.borrow_mut().push(Event::Cancelled(id)));
+    }
+}
+
+type TestRequests = Requests<TestStore, TestHook>;
+
+fn events() -> Vec<Event> {
+    EVENTS.with(|events| events.borrow_mut().drain(..).collect())
+}
+
+#[test]
+fn request_and_fulfill() {
+    let info = test_chain(Scheme::PedersenBlsUnchained);
+    // round 10 is the latest one
+    let now = info.round_time(10) + 5;
+
+    assert_eq!(
+        TestRequests::request(&info, 1, 10, 60, now),
+        Err(RequestError::RoundNotInFuture)
+    );
+    TestRequests::request(&info, 1, 11, 60, now).unwrap();
+    TestRequests::request(&info, 2, 11, 60, now).unwrap();
+    TestRequests::request(&info, 3, 12, 60, now).unwrap();
+    assert_eq!(
+        TestRequests::request(&info, 1, 13, 60, now),
+        Err(RequestError::DuplicateRequest)
+    );
+    assert_eq!(
+        TestRequests::request(&info, 4, 13, 60, now),
+        Err(RequestError::StoreFull)
+    );
+
+    assert_eq!(TestRequests::due_rounds(&info, now), Vec::<u64>::new());
+    let now = info.round_time(12);
+    assert_eq!(TestRequests::due_rounds(&info, now), vec![11, 12]);
+
+    // a bogus round is rejected, and the requests stay pending
+    let mut bogus = sign(&info, 11);
+    bogus.round = 12;
+    assert_eq!(
+        TestRequests::fulfill(&info, &bogus),
+        Err(RequestError::Verification(ClientError::InvalidSignature))
+    );
+    assert_eq!(
+        TestRequests::fulfill(&info, &sign(&info, 13)),
+        Err(RequestError::UnknownRequest)
+    );
+
+    let round = sign(&info, 11);
+    assert_eq!(TestRequests::fulfill(&info, &round), Ok(2));
+    assert_eq!(
+        events(),
+        vec![
+            Event::Fulfilled(1, 11, round.derive(REQUEST_CONTEXT, 1)),
+            Event::Fulfilled(2, 11, round.derive(REQUEST_CONTEXT, 2)),
+        ]
+    );
+    assert_eq!(TestRequests::due_rounds(&info, now), vec![12]);
+
+    assert_eq!(TestRequests::cancel(3), Ok(()));
+    assert_eq!(TestRequests::cancel(3), Err(RequestError::UnknownRequest));
+    assert_eq!(events(), vec![Event::Cancelled(3)]);
+}
+
+#[test]
+fn expire() {
+    let info = test_chain(Scheme::PedersenBlsUnchained);
+    let now = info.round_time(10);
+
+    TestRequests::request(&info, 1, 11, 60, now).unwrap();
+    TestRequests::request(&info, 2, 20, 60, now).unwrap();
+
+    assert_eq!(TestRequests::expire(info.round_time(11) + 59), 0);
+    // an expired request isn't due anymore, even before `expire` runs
+    assert_eq!(
+        TestRequests::due_rounds(&info, info.round_time(11) + 60),
+        Vec::<u64>::new()
+    );
+    assert_eq!(TestRequests::expire(info.round_time(11) + 60), 1);
+    assert_eq!(events(), vec![Event::Timeout(1)]);
+    assert_eq!(TestStore::pending().len(), 1);
+}
+
+#[test]
+fn fetch_due_rounds() {
+    let info = test_chain(Scheme::BlsUnchainedG1Rfc9380);
+    let now = info.round_time(10);
+    TestRequests::request(&info, 1, 11, 60, now).unwrap();
+    TestRequests::request(&info, 2, 12, 60, now).unwrap();
+
+    // round 12 isn't served yet
+    let round = sign(&info, 11);
+    let mut transport = MockTransport::new();
+    transport.insert(
+        "http://localhost/public/11",
+        HttpResponse::ok(round_json(&round)),
+    );
+
+    let client = Client::new("http://localhost", info.clone()).unwrap();
+    let fetched = TestRequests::fetch_due_with(&client, &transport, info.round_time(12));
+    assert_eq!(fetched, vec![round]);
+
+    for round in &fetched {
+        TestRequests::fulfill(&info, round).unwrap();
+    }
+    assert_eq!(events().len(), 1);
+    assert_eq!(
+        TestRequests::due_rounds(&info, info.round_time(12)),
+        vec![12]
+    );
+}
@@ -0,0 +1,185 @@
+//! Retries within a run, and backoff across runs.
+
+use sp_io::TestExternalities;
+use sp_runtime::offchain::{testing, OffchainDbExt, OffchainWorkerExt, Timestamp};
+
+use super::local_client;
+use crate::{Client, ClientError, HttpResponse, MockTransport, RetryConfig, RetryState};
+
+const URL: &str = "http://localhost/public/latest";
+const URL_DOWN: &str = "http://localhost/public/1000";
+
+fn client_with_retry() -> Client {
+    let mut client = local_client();
+    client.set_retry(Some(RetryConfig {
+        max_attempts: 3,
+        base_delay_ms: 1_000,
+        max_delay_ms: 10_000,
+    }));
+    client
+}
+
+fn status(code: u16) -> HttpResponse {
+    HttpResponse {
+        code,
+        body: vec![],
+        headers: vec![],
+    }
+}
+
+#[test]
+fn backoff_delay() {
+    let config = RetryConfig {
+        max_attempts: 1,
+        base_delay_ms: 1_000,
+        max_delay_ms: 10_000,
+    };
+    assert_eq!(config.delay(1, [0; 32]), 1_000);
+    assert_eq!(config.delay(2, [0; 32]), 2_000);
+    assert_eq!(config.delay(4, [0; 32]), 8_000);
+    assert_eq!(config.delay(5, [0; 32]), 10_000);
+    assert_eq!(config.delay(u32::MAX, [0; 32]), 10_000);
+    let unbounded = RetryConfig {
+        max_delay_ms: u64::MAX,
+        ..config.clone()
+    };
+    assert_eq!(unbounded.delay(u32::MAX, [0xff; 32]), u64::MAX);
+
+    // jitter adds up to a quarter of the delay
+    let mut seed = [0; 32];
+    seed[0] = 100;
+    assert_eq!(config.delay(1, seed), 1_100);
+    assert!((0..=255).all(|byte| {
+        seed[0] = byte;
+        seed[1] = byte;
+        (10_000..=12_500).contains(&config.delay(10, seed))
+    }));
+}
+
+#[test]
+fn longest_wait_between_attempts() {
+    let config = RetryConfig {
+        max_attempts: 1,
+        base_delay_ms: 1_000,
+        max_delay_ms: 10_000,
+    };
+    assert_eq!(config.max_wait_ms(), 0);
+    let config = RetryConfig {
+        max_attempts: 4,
+        ..config
+    };
+    // 1s, 2s and 4s, plus a quarter each
+    assert_eq!(config.max_wait_ms(), 8_750);
+    let config = RetryConfig {
+        max_attempts: 100,
+        ..config
+    };
+    assert_eq!(config.max_wait_ms(), 18_750 + 95 * 12_500);
+    let config = RetryConfig {
+        max_attempts: u32::MAX,
+        max_delay_ms: u64::MAX,
+        ..config
+    };
+    assert_eq!(config.max_wait_ms(), u64::MAX);
+}
+
+#[test]
+fn retries_within_a_run() {
+    let (offchain, state) = testing::TestOffchainExt::new();
+    let mut t = TestExternalities::default();
+    t.register_extension(OffchainDbExt::new(offchain.clone()));
+    t.register_extension(OffchainWorkerExt::new(offchain));
+
+    let mut transport = MockTransport::new().with_response(URL, b"{}");
+    transport.insert(URL_DOWN, status(503));
+
+    t.execute_with(|| {
+        state.write().timestamp = Timestamp::from_unix_millis(1_000_000);
+        let client = client_with_retry();
+        client.make_request_with(&transport, URL.into()).unwrap();
+        assert_eq!(transport.requests().len(), 1);
+
+        // the attempts are spaced out: 1s, then 2s
+        assert_eq!(
+            client.make_request_with(&transport, URL_DOWN.into()),
+            Err(ClientError::Http)
+        );
+        assert_eq!(transport.requests().len(), 4);
+        assert_eq!(state.read().timestamp.unix_millis(), 1_003_000);
+    })
+}
+
+#[test]
+fn only_endpoint_failures_are_retried() {
+    let (offchain, state) = testing::TestOffchainExt::new();
+    let mut t = TestExternalities::default();
+    t.register_extension(OffchainDbExt::new(offchain.clone()));
+    t.register_extension(OffchainWorkerExt::new(offchain));
+
+    let mut transport = MockTransport::new();
+    transport.insert(URL, status(404));
+    transport.insert(URL_DOWN, status(429));
+
+    t.execute_with(|| {
+        state.write().timestamp = Timestamp::from_unix_millis(1_000_000);
+        let client = client_with_retry();
+
+        // a round not emitted yet: the endpoint works, it's neither retried nor backed off
+        assert_eq!(
+            client.make_request_with(&transport, URL.into()),
+            Err(ClientError::Http)
+        );
+        assert_eq!(transport.requests().len(), 1);
+        assert_eq!(RetryState::get(b"http://localhost"), RetryState::default());
+
+        client
+            .make_request_with(&transport, URL_DOWN.into())
+            .unwrap_err();
+        assert_eq!(transport.requests().len(), 4);
+        assert_eq!(RetryState::get(b"http://localhost").failures, 1);
+    })
+}
+
+#[test]
+fn backs_off_across_runs() {
+    let (offchain, state) = testing::TestOffchainExt::new();
+    let mut t = TestExternalities::default();
+    t.register_extension(OffchainDbExt::new(offchain.clone()));
+    t.register_extension(OffchainWorkerExt::new(offchain));
+
+    let mut down = MockTransport::new();
+    down.insert(URL, status(503));
+    let up = MockTransport::new().with_response(URL, b"{}");
+
+    t.execute_with(|| {
+        state.write().timestamp = Timestamp::from_unix_millis(1_000_000);
+        let client = client_with_retry();
+
+        // the backoff starts after the last attempt, 3s later
+        client.make_request_with(&down, URL.into()).unwrap_err();
+        assert_eq!(
+            RetryState::get(b"http://localhost"),
+            RetryState {
+                failures: 1,
+                retry_at: 1_004_000
+            }
+        );
+
+        // nothing goes out while backed off
+        assert_eq!(
+            client.make_request_with(&up, URL.into()),
+            Err(ClientError::RetryLater)
+        );
+        assert!(up.requests().is_empty());
+
+        state.write().timestamp = Timestamp::from_unix_millis(1_004_000);
+        client.make_request_with(&down, URL.into()).unwrap_err();
+        assert_eq!(RetryState::get(b"http://localhost").retry_at, 1_009_000);
+        assert_eq!(down.requests().len(), 6);
+
+        // a successful request clears the backoff
+        state.write().timestamp = Timestamp::from_unix_millis(1_009_000);
+        client.make_request_with(&up, URL.into()).unwrap();
+        assert_eq!(RetryState::get(b"http://localhost"), RetryState::default());
+    })
+}
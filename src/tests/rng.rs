@@ -0,0 +1,82 @@
+//! Randomness extraction helpers.
+
+use crate::{util::hex_to_vec_u8, RoundRng, MAX_SAMPLE_SIZE};
+
+const RANDOMNESS: &str = "ccbdad137f3bc5e01ebd8c7529abc31813a0566b84e6fd765a661398e9bcbc2f";
+
+fn rng(context: &[u8]) -> RoundRng {
+    RoundRng::new(&hex_to_vec_u8(RANDOMNESS).unwrap(), context)
+}
+
+#[test]
+fn stream_is_reproducible() {
+    // recomputed from the documented algorithm with Python's `hashlib` and `cryptography`
+    let expected = [
+        14750180118955160854,
+        15011220956682483992,
+        1412627320215958667,
+        14698708033776298313,
+    ];
+    let mut a = rng(b"lottery");
+    assert_eq!((0..4).map(|_| a.next_u64()).collect::<Vec<_>>(), expected);
+
+    assert_ne!(rng(b"raffle").next_u64(), expected[0]);
+}
+
+#[test]
+fn uniform() {
+    let mut r = rng(b"dice");
+    let mut seen = [false; 6];
+    for _ in 0..1000 {
+        let roll = r.uniform(1..7).unwrap();
+        assert!((1..7).contains(&roll));
+        seen[roll as usize - 1] = true;
+    }
+    assert!(seen.iter().all(|s| *s));
+
+    assert_eq!(r.uniform(5..5), None);
+    assert_eq!(r.uniform(0..1), Some(0));
+    assert!(r.uniform(0..u64::MAX).is_some());
+}
+
+#[test]
+fn shuffle_and_sample() {
+    let mut items: Vec<u64> = (0..50).collect();
+    rng(b"shuffle").shuffle(&mut items);
+    assert_ne!(items, (0..50).collect::<Vec<_>>());
+    let mut sorted = items.clone();
+    sorted.sort();
+    assert_eq!(sorted, (0..50).collect::<Vec<_>>());
+
+    // a sample is a prefix of the shuffle drawn from the same stream
+    assert_eq!(rng(b"shuffle").sample(10, 50).unwrap(), items[..10]);
+    assert_eq!(rng(b"shuffle").sample(50, 50).unwrap(), items);
+
+    let sample = rng(b"committee").sample(5, u64::MAX).unwrap();
+    assert_eq!(sample.len(), 5);
+    assert!(sample
+        .iter()
+        .all(|i| sample.iter().filter(|j| *j == i).count() == 1));
+    assert_eq!(rng(b"committee").sample(6, 5), None);
+    assert_eq!(rng(b"committee").sample(0, 0), Some(vec![]));
+    assert_eq!(
+        rng(b"committee").sample(MAX_SAMPLE_SIZE + 1, u64::MAX),
+        None
+    );
+    assert_eq!(rng(b"committee").sample(u64::MAX, u64::MAX), None);
+}
+
+#[test]
+fn choose_weighted() {
+    let mut r = rng(b"weighted");
+    let mut counts = [0u32; 4];
+    for _ in 0..1000 {
+        counts[r.choose_weighted(&[1, 0, 3, 0]).unwrap()] += 1;
+    }
+    assert_eq!(counts[1] + counts[3], 0);
+    assert!(counts[2] > counts[0]);
+
+    assert_eq!(r.choose_weighted(&[]), None);
+    assert_eq!(r.choose_weighted(&[0, 0]), None);
+    assert_eq!(r.choose_weighted(&[u64::MAX, 1]), None);
+}
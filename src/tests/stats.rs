@@ -0,0 +1,147 @@
+//! Endpoint statistics, and endpoint selection from them.
+
+use sp_io::TestExternalities;
+use sp_runtime::offchain::{testing, OffchainDbExt, OffchainWorkerExt, Timestamp};
+
+use super::local_client;
+use crate::{Client, EndpointStats, HttpResponse, MockTransport, StatsConfig};
+
+const PRIMARY: &str = "http://localhost";
+const RELAY: &str = "http://relay";
+
+fn client_with_stats() -> Client {
+    let mut client = local_client();
+    client.set_relays(&[RELAY]).unwrap();
+    client.set_stats(Some(StatsConfig::default()));
+    client
+}
+
+fn status(code: u16) -> HttpResponse {
+    HttpResponse {
+        code,
+        body: vec![],
+        headers: vec![],
+    }
+}
+
+#[test]
+fn requests_are_recorded() {
+    let (offchain, state) = testing::TestOffchainExt::new();
+    let mut t = TestExternalities::default();
+    t.register_extension(OffchainDbExt::new(offchain.clone()));
+    t.register_extension(OffchainWorkerExt::new(offchain));
+
+    let mut transport = MockTransport::new().with_response("http://localhost/ok", b"{}");
+    transport.insert("http://localhost/not-yet", status(404));
+    transport.insert("http://localhost/down", status(503));
+
+    t.execute_with(|| {
+        state.write().timestamp = Timestamp::from_unix_millis(1_000_000);
+        let client = client_with_stats();
+
+        client
+            .make_request_with(&transport, b"http://localhost/ok".to_vec())
+            .unwrap();
+        // answered by the endpoint, not a failure of it
+        client
+            .make_request_with(&transport, b"http://localhost/not-yet".to_vec())
+            .unwrap_err();
+        client
+            .make_request_with(&transport, b"http://localhost/missing".to_vec())
+            .unwrap_err();
+        client
+            .make_request_with(&transport, b"http://localhost/down".to_vec())
+            .unwrap_err();
+
+        let stats = client.endpoint_stats();
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats[0].0.as_str(), PRIMARY);
+        assert_eq!(
+            stats[0].1,
+            EndpointStats {
+                requests: 4,
+                failures: 2,
+                consecutive_failures: 2,
+                latency_ms: 0,
+                last_failure: Some(1_000_000),
+            }
+        );
+        assert_eq!(stats[0].1.error_rate(), 50);
+        assert_eq!(stats[1].1, EndpointStats::default());
+
+        // nothing is recorded without `stats`
+        local_client()
+            .make_request_with(&transport, b"http://localhost/ok".to_vec())
+            .unwrap();
+        assert_eq!(EndpointStats::get(PRIMARY.as_bytes()).requests, 4);
+    })
+}
+
+#[test]
+fn latency_is_averaged() {
+    let (offchain, _state) = testing::TestOffchainExt::new();
+    let mut t = TestExternalities::default();
+    t.register_extension(OffchainDbExt::new(offchain.clone()));
+    t.register_extension(OffchainWorkerExt::new(offchain));
+
+    t.execute_with(|| {
+        EndpointStats::record(PRIMARY.as_bytes(), true, 800, 0);
+        assert_eq!(EndpointStats::get(PRIMARY.as_bytes()).latency_ms, 800);
+
+        EndpointStats::record(PRIMARY.as_bytes(), false, 5_000, 0);
+        EndpointStats::record(PRIMARY.as_bytes(), true, 0, 0);
+        let stats = EndpointStats::get(PRIMARY.as_bytes());
+        assert_eq!(stats.latency_ms, 700);
+        assert_eq!(stats.consecutive_failures, 0);
+    })
+}
+
+#[test]
+fn prefers_fast_healthy_endpoints() {
+    let (offchain, state) = testing::TestOffchainExt::new();
+    let mut t = TestExternalities::default();
+    t.register_extension(OffchainDbExt::new(offchain.clone()));
+    t.register_extension(OffchainWorkerExt::new(offchain));
+
+    t.execute_with(|| {
+        state.write().timestamp = Timestamp::from_unix_millis(1_000_000);
+        let client = client_with_stats();
+
+        // endpoints never used are tried first
+        EndpointStats::record(PRIMARY.as_bytes(), true, 300, 0);
+        assert_eq!(client.preferred_endpoint().endpoint.as_str(), RELAY);
+
+        EndpointStats::record(RELAY.as_bytes(), true, 100, 0);
+        assert_eq!(client.preferred_endpoint().endpoint.as_str(), RELAY);
+
+        // the relay keeps failing, and is avoided until it's due for a probe
+        for _ in 0..3 {
+            EndpointStats::record(RELAY.as_bytes(), false, 0, 990_000);
+        }
+        let preferred = client.preferred_endpoint();
+        assert_eq!(preferred.endpoint.as_str(), PRIMARY);
+        assert_eq!(preferred.stats, client.stats);
+
+        state.write().timestamp = Timestamp::from_unix_millis(990_000 + 60_000);
+        assert_eq!(client.preferred_endpoint().endpoint.as_str(), RELAY);
+    })
+}
+
+#[test]
+fn falls_back_to_least_recently_failed() {
+    let (offchain, state) = testing::TestOffchainExt::new();
+    let mut t = TestExternalities::default();
+    t.register_extension(OffchainDbExt::new(offchain.clone()));
+    t.register_extension(OffchainWorkerExt::new(offchain));
+
+    t.execute_with(|| {
+        state.write().timestamp = Timestamp::from_unix_millis(1_000_000);
+        let client = client_with_stats();
+
+        for _ in 0..3 {
+            EndpointStats::record(PRIMARY.as_bytes(), false, 0, 999_000);
+            EndpointStats::record(RELAY.as_bytes(), false, 0, 998_000);
+        }
+        assert_eq!(client.preferred_endpoint().endpoint.as_str(), RELAY);
+    })
+}
@@ -0,0 +1,178 @@
+//! `tle`/age format, binary and armored.
+
+use base64::{
+    engine::general_purpose::{STANDARD, STANDARD_NO_PAD},
+    Engine,
+};
+use chacha20poly1305::{aead::Aead, ChaCha20Poly1305, KeyInit};
+use hkdf::Hkdf;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::{
+    tle::{self, TleError},
+    util::vec_u8_to_hex,
+    Scheme, QUICKNET,
+};
+
+use super::tlock::{quicknet_round_1000, sign, test_chain};
+
+const ENTROPY: [u8; 64] = [9u8; 64];
+
+#[test]
+fn binary_round_trip() {
+    for scheme in [Scheme::PedersenBlsUnchained, Scheme::BlsUnchainedG1Rfc9380] {
+        let info = test_chain(scheme);
+        let plaintext = b"reveal at round 1000";
+
+        let file = tle::encrypt(&info, 1000, plaintext, &ENTROPY).unwrap();
+        let expected_intro = format!(
+            "age-encryption.org/v1\n-> tlock 1000 {}\n",
+            vec_u8_to_hex(&info.hash)
+        );
+        assert!(file.starts_with(expected_intro.as_bytes()));
+
+        let header = tle::parse_header(&file).unwrap();
+        assert_eq!(header.round, 1000);
+        assert_eq!(header.chain_hash, info.hash);
+
+        assert_eq!(
+            tle::decrypt(&info, &sign(&info, 1000), &file).unwrap(),
+            plaintext
+        );
+    }
+}
+
+#[test]
+fn armored_round_trip() {
+    let info = test_chain(Scheme::BlsUnchainedG1Rfc9380);
+    let file = tle::encrypt(&info, 7, b"armored", &ENTROPY).unwrap();
+
+    let armored = tle::armor(&file);
+    assert!(armored.starts_with("-----BEGIN AGE ENCRYPTED FILE-----\n"));
+    assert!(armored.ends_with("-----END AGE ENCRYPTED FILE-----\n"));
+    assert!(armored.lines().all(|line| line.len() <= 64));
+    assert_eq!(tle::dearmor(&armored).unwrap(), file);
+
+    assert_eq!(
+        tle::decrypt(&info, &sign(&info, 7), armored.as_bytes()).unwrap(),
+        b"armored"
+    );
+    assert_eq!(tle::parse_header(armored.as_bytes()).unwrap().round, 7);
+}
+
+#[test]
+fn multi_chunk_and_empty_payloads() {
+    let info = test_chain(Scheme::PedersenBlsUnchained);
+    let round = sign(&info, 3);
+
+    for plaintext in [vec![], vec![0xab; 64 * 1024], vec![0xcd; 150_000]] {
+        let file = tle::encrypt(&info, 3, &plaintext, &ENTROPY).unwrap();
+        assert_eq!(tle::decrypt(&info, &round, &file).unwrap(), plaintext);
+    }
+}
+
+#[test]
+fn decrypt_errors() {
+    let info = test_chain(Scheme::BlsUnchainedG1Rfc9380);
+    let file = tle::encrypt(&info, 10, b"secret", &ENTROPY).unwrap();
+
+    assert_eq!(
+        tle::decrypt(&info, &sign(&info, 11), &file),
+        Err(TleError::WrongRound)
+    );
+
+    let mut other_chain = test_chain(Scheme::BlsUnchainedG1Rfc9380);
+    other_chain.hash = vec![1u8; 32].try_into().unwrap();
+    assert_eq!(
+        tle::decrypt(&other_chain, &sign(&other_chain, 10), &file),
+        Err(TleError::WrongChain)
+    );
+
+    // flip a bit of the round in the stanza, which is covered by the MAC and the signature
+    let tampered = String::from_utf8_lossy(&file).replacen("tlock 10 ", "tlock 11 ", 1);
+    assert!(tle::decrypt(&info, &sign(&info, 11), tampered.as_bytes()).is_err());
+
+    let mut truncated = file.clone();
+    truncated.pop();
+    assert_eq!(
+        tle::decrypt(&info, &sign(&info, 10), &truncated),
+        Err(TleError::InvalidPayload)
+    );
+
+    assert_eq!(
+        tle::parse_header(b"age-encryption.org/v1\n-> X25519 abc\n\n--- AAAA\n"),
+        Err(TleError::UnsupportedRecipient)
+    );
+    assert_eq!(
+        tle::parse_header(b"not an age file"),
+        Err(TleError::InvalidFormat)
+    );
+}
+
+#[test]
+fn quicknet_file_follows_the_age_spec() {
+    // every part of the file is rebuilt here from the age v1 spec, rather than with the parser
+    let info = QUICKNET.info();
+    let file = tle::encrypt(&info, 1000, b"hello quicknet", &ENTROPY).unwrap();
+    let (file_key, nonce) = (&ENTROPY[..16], &ENTROPY[48..]);
+
+    let mut lines = file.split(|b| *b == b'\n');
+    assert_eq!(lines.next().unwrap(), b"age-encryption.org/v1");
+    assert_eq!(
+        lines.next().unwrap(),
+        format!("-> tlock 1000 {}", QUICKNET.chain_hash).as_bytes()
+    );
+    // U on G2 (96 bytes), V and W (16 bytes each): 171 base64 characters, wrapped at 64
+    let body: Vec<&[u8]> = lines.by_ref().take(3).collect();
+    assert_eq!(
+        body.iter().map(|line| line.len()).collect::<Vec<_>>(),
+        vec![64, 64, 43]
+    );
+    assert_eq!(STANDARD_NO_PAD.decode(body.concat()).unwrap().len(), 128);
+
+    let mac_line = lines.next().unwrap();
+    let header_len = file.windows(4).position(|w| w == b"\n---").unwrap() + 4;
+    let mut mac_key = [0u8; 32];
+    Hkdf::<Sha256>::new(Some(&[]), file_key)
+        .expand(b"header", &mut mac_key)
+        .unwrap();
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(&mac_key).unwrap();
+    mac.update(&file[..header_len]);
+    assert_eq!(
+        mac_line,
+        format!(
+            "--- {}",
+            STANDARD_NO_PAD.encode(mac.finalize().into_bytes())
+        )
+        .as_bytes()
+    );
+
+    // a single, last, STREAM chunk after the 16 bytes nonce
+    let mac_line_start = header_len - b"---".len();
+    let payload_start = mac_line_start + mac_line.len() + 1;
+    assert_eq!(&file[payload_start..payload_start + 16], nonce);
+    let mut payload_key = [0u8; 32];
+    Hkdf::<Sha256>::new(Some(nonce), file_key)
+        .expand(b"payload", &mut payload_key)
+        .unwrap();
+    let mut chunk_nonce = [0u8; 12];
+    chunk_nonce[11] = 1;
+    let payload = ChaCha20Poly1305::new(&payload_key.into())
+        .decrypt(&chunk_nonce.into(), &file[payload_start + 16..])
+        .unwrap();
+    assert_eq!(payload, b"hello quicknet");
+
+    let armored = tle::armor(&file);
+    let encoded: String = armored
+        .lines()
+        .filter(|line| !line.starts_with("-----"))
+        .collect();
+    assert_eq!(STANDARD.decode(encoded).unwrap(), file);
+
+    // and the file key opens with the signature published by quicknet
+    assert_eq!(
+        tle::decrypt(&info, &quicknet_round_1000(), armored.as_bytes()).unwrap(),
+        b"hello quicknet"
+    );
+}
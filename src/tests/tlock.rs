@@ -0,0 +1,235 @@
+//! Timelock encryption, and verification of unchained rounds, against test chains with a known
+//! secret key and against a round published by the League of Entropy `quicknet` chain.
+
+use ark_bls12_381::{Bls12_381, Fq, Fr, G1Affine, G2Affine};
+use ark_ec::{pairing::Pairing, AffineRepr, CurveGroup};
+use ark_ff::{BigInteger, PrimeField};
+use ark_serialize::CanonicalSerialize;
+use drand_verify::derive_randomness;
+
+use crate::{
+    bls::{hash_to_signature_group, SignaturePoint},
+    round_message,
+    tlock::{self, TlockError},
+    util::{hex_to_vec_u8, vec_u8_to_hex},
+    Client, ClientError, Info, Round, Scheme, QUICKNET,
+};
+
+const SECRET_KEY: u64 = 0x5ec7e7;
+
+fn compress<P: CanonicalSerialize>(point: &P) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    point.serialize_compressed(&mut bytes).unwrap();
+    bytes
+}
+
+/// A chain of the given scheme, with `SECRET_KEY` as its secret key.
+pub(super) fn test_chain(scheme: Scheme) -> Info {
+    let sk = Fr::from(SECRET_KEY);
+    let public_key = if scheme.public_key_on_g2() {
+        compress(&(G2Affine::generator() * sk).into_affine())
+    } else {
+        compress(&(G1Affine::generator() * sk).into_affine())
+    };
+    Info {
+        public_key: public_key.try_into().unwrap(),
+        scheme,
+        ..Info::default()
+    }
+}
+
+/// Round `round` of a chain created with `test_chain`.
+pub(super) fn sign(info: &Info, round: u64) -> Round {
+    let sk = Fr::from(SECRET_KEY);
+    let msg = round_message(info.scheme, round, &[]);
+    let signature = match hash_to_signature_group(info.scheme, &msg).unwrap() {
+        SignaturePoint::G1(point) => compress(&(point * sk).into_affine()),
+        SignaturePoint::G2(point) => compress(&(point * sk).into_affine()),
+    };
+    Round {
+        round,
+        randomness: derive_randomness(&signature).to_vec().try_into().unwrap(),
+        previous_signature: Default::default(),
+        signature: signature.try_into().unwrap(),
+    }
+}
+
+/// Round 1000 of `quicknet`, as served by `https://api.drand.sh/{chain hash}/public/1000`.
+pub(super) fn quicknet_round_1000() -> Round {
+    let hex = |hex| hex_to_vec_u8(hex).unwrap();
+    Round {
+        round: 1000,
+        randomness: hex("fe290beca10872ef2fb164d2aa4442de4566183ec51c56ff3cd603d930e54fdd")
+            .try_into()
+            .unwrap(),
+        previous_signature: Default::default(),
+        signature: hex("b44679b9a59af2ec876b1a6b1ad52ea9b1615fc3982b19576350f93447cb1125e342b73a8dd2bacbe47e4b6b63ed5e39")
+            .try_into()
+            .unwrap(),
+    }
+}
+
+/// JSON body of an unchained round, as served by the HTTP API.
+pub(super) fn round_json(round: &Round) -> Vec<u8> {
+    serde_json::json!({
+        "round": round.round,
+        "randomness": vec_u8_to_hex(&round.randomness),
+        "signature": vec_u8_to_hex(&round.signature),
+    })
+    .to_string()
+    .into_bytes()
+}
+
+#[test]
+fn verify_unchained_rounds() {
+    for scheme in [
+        Scheme::PedersenBlsUnchained,
+        Scheme::BlsUnchainedOnG1,
+        Scheme::BlsUnchainedG1Rfc9380,
+    ] {
+        let info = test_chain(scheme);
+        let round = sign(&info, 1000);
+        assert!(Client::verify_round(&round, &info).is_ok());
+
+        let forged = Round {
+            round: 1001,
+            ..round.clone()
+        };
+        assert_eq!(
+            Client::verify_round(&forged, &info),
+            Err(ClientError::InvalidSignature)
+        );
+
+        let wrong_randomness = Round {
+            randomness: [0u8; 32].to_vec().try_into().unwrap(),
+            ..round
+        };
+        assert_eq!(
+            Client::verify_round(&wrong_randomness, &info),
+            Err(ClientError::RandomnessVerificationError)
+        );
+    }
+}
+
+#[test]
+fn encrypt_decrypt_round_trip() {
+    for scheme in [Scheme::PedersenBlsUnchained, Scheme::BlsUnchainedG1Rfc9380] {
+        let info = test_chain(scheme);
+        let msg = b"sealed bid: 42 units";
+
+        let ciphertext = tlock::encrypt(&info, 1000, msg, &[7u8; 32]).unwrap();
+        let expected_u_len = if scheme.public_key_on_g2() { 96 } else { 48 };
+        assert_eq!(ciphertext.u.len(), expected_u_len);
+        assert_eq!(ciphertext.v.len(), msg.len());
+
+        let decrypted = tlock::decrypt(&info, &sign(&info, 1000), &ciphertext).unwrap();
+        assert_eq!(decrypted, msg);
+
+        // another round's signature is valid, but can't decrypt
+        assert_eq!(
+            tlock::decrypt(&info, &sign(&info, 999), &ciphertext),
+            Err(TlockError::DecryptionFailed)
+        );
+    }
+}
+
+#[test]
+fn decrypt_rejects_invalid_round() {
+    let info = test_chain(Scheme::BlsUnchainedG1Rfc9380);
+    let ciphertext = tlock::encrypt(&info, 1000, b"secret", &[1u8; 32]).unwrap();
+
+    let forged = Round {
+        round: 1000,
+        ..sign(&info, 1001)
+    };
+    assert_eq!(
+        tlock::decrypt(&info, &forged, &ciphertext),
+        Err(TlockError::InvalidSignature)
+    );
+}
+
+#[test]
+fn tampered_ciphertext_fails() {
+    let info = test_chain(Scheme::PedersenBlsUnchained);
+    let mut ciphertext = tlock::encrypt(&info, 5, b"secret", &[2u8; 32]).unwrap();
+    ciphertext.w[0] ^= 1;
+
+    assert_eq!(
+        tlock::decrypt(&info, &sign(&info, 5), &ciphertext),
+        Err(TlockError::DecryptionFailed)
+    );
+}
+
+#[test]
+fn encrypt_rejects_chained_scheme_and_long_messages() {
+    assert_eq!(
+        tlock::encrypt(&Info::default(), 1, b"secret", &[0u8; 32]),
+        Err(TlockError::ChainedScheme)
+    );
+
+    let info = test_chain(Scheme::BlsUnchainedG1Rfc9380);
+    assert_eq!(
+        tlock::encrypt(&info, 1, &[0u8; 33], &[0u8; 32]),
+        Err(TlockError::InvalidMessageLength)
+    );
+}
+
+#[test]
+fn verify_published_quicknet_round() {
+    let info = QUICKNET.info();
+    let round = quicknet_round_1000();
+    assert_eq!(
+        Client::verify_round(&round, &info).unwrap(),
+        round.randomness
+    );
+
+    assert_eq!(
+        Client::verify_round(
+            &Round {
+                round: 1001,
+                ..round
+            },
+            &info
+        ),
+        Err(ClientError::InvalidSignature)
+    );
+}
+
+#[test]
+fn decrypt_with_published_signature() {
+    // the identity of a round is what the chain signs: a round published by drand is the key
+    // of the ciphertexts encrypted to it
+    let info = QUICKNET.info();
+    let msg = b"opened by quicknet round 1000";
+
+    let ciphertext = tlock::encrypt(&info, 1000, msg, &[3u8; 32]).unwrap();
+    assert_eq!(ciphertext.u.len(), 96);
+    assert_eq!(
+        tlock::decrypt(&info, &quicknet_round_1000(), &ciphertext).unwrap(),
+        msg
+    );
+
+    let ciphertext = tlock::encrypt(&info, 1001, msg, &[3u8; 32]).unwrap();
+    assert_eq!(
+        tlock::decrypt(&info, &quicknet_round_1000(), &ciphertext),
+        Err(TlockError::DecryptionFailed)
+    );
+}
+
+#[test]
+fn target_group_serialization() {
+    // kyber writes each Fp12 element as c1 || c0, each Fp6 as c2 || c1 || c0 and each Fp2 as
+    // c1 || c0, every Fp being 48 bytes big-endian
+    let gt = Bls12_381::pairing(G1Affine::generator(), G2Affine::generator());
+    let fp_bytes = |fp: &Fq| fp.into_bigint().to_bytes_be();
+
+    let mut expected = Vec::new();
+    for fp6 in [&gt.0.c1, &gt.0.c0] {
+        for fp2 in [&fp6.c2, &fp6.c1, &fp6.c0] {
+            expected.extend(fp_bytes(&fp2.c1));
+            expected.extend(fp_bytes(&fp2.c0));
+        }
+    }
+    assert_eq!(expected.len(), 576);
+    assert_eq!(tlock::gt_to_bytes(&gt), expected);
+}
@@ -0,0 +1,109 @@
+//! The same client logic, driven by the in-memory `MockTransport` instead of the offchain HTTP API.
+
+use std::fs;
+
+use sp_io::TestExternalities;
+use sp_runtime::offchain::{testing, OffchainWorkerExt};
+
+use super::local_client;
+use crate::{ClientError, HttpResponse, MockTransport, OffchainTransport, Transport};
+
+fn testdata(name: &str) -> Vec<u8> {
+    fs::read(format!("./src/tests/testdata/{name}")).unwrap()
+}
+
+#[test]
+fn mock_get_info() {
+    let transport =
+        MockTransport::new().with_response("http://localhost/info", &testdata("chain_info.json"));
+
+    let info = local_client().info_with(&transport).unwrap();
+    assert_eq!(info.period, 30);
+    assert_eq!(transport.requests(), vec!["http://localhost/info"]);
+}
+
+#[test]
+fn mock_get_round_verified() {
+    let transport = MockTransport::new()
+        .with_response("http://localhost/public/2458190", &testdata("latest.json"));
+
+    let round = local_client().round_with(&transport, 2458190).unwrap();
+    assert_eq!(round.round, 2458190);
+}
+
+#[test]
+fn mock_get_round_invalid_signature() {
+    // claim round 2458190's signature belongs to the next round, it can't verify
+    let body = String::from_utf8(testdata("latest.json"))
+        .unwrap()
+        .replace("2458190", "2458191");
+    let transport =
+        MockTransport::new().with_response("http://localhost/public/2458191", body.as_bytes());
+
+    let round = local_client().round_with(&transport, 2458191);
+    assert_eq!(round, Err(ClientError::InvalidSignature));
+}
+
+#[test]
+fn mock_unexpected_status_code() {
+    let mut transport = MockTransport::new();
+    transport.insert(
+        "http://localhost/public/latest",
+        HttpResponse {
+            code: 404,
+            body: vec![],
+            headers: vec![],
+        },
+    );
+
+    assert_eq!(
+        local_client().latest_with(&transport),
+        Err(ClientError::Http)
+    );
+}
+
+#[test]
+fn mock_missing_response() {
+    let transport = MockTransport::new();
+
+    assert_eq!(
+        local_client().chains_with(&transport),
+        Err(ClientError::Http)
+    );
+}
+
+#[test]
+fn offchain_body_size_limit() {
+    let (offchain, state) = testing::TestOffchainExt::new();
+    let mut t = TestExternalities::default();
+    t.register_extension(OffchainWorkerExt::new(offchain));
+
+    let expect = |response: Vec<u8>, response_headers: Vec<(String, String)>| {
+        state.write().expect_request(testing::PendingRequest {
+            method: "GET".into(),
+            uri: "http://localhost/public/latest".into(),
+            sent: true,
+            response: Some(response),
+            response_headers,
+            ..Default::default()
+        });
+    };
+
+    t.execute_with(|| {
+        let transport = OffchainTransport::new().with_max_body_size(16);
+        let url = "http://localhost/public/latest";
+
+        expect(vec![b'a'; 16], vec![]);
+        assert_eq!(transport.get(url).unwrap().body, vec![b'a'; 16]);
+
+        expect(vec![b'a'; 17], vec![]);
+        assert_eq!(transport.get(url), Err(ClientError::ResponseTooLarge));
+
+        // a declared length over the limit fails before reading
+        expect(
+            vec![b'a'; 8],
+            vec![("Content-Length".into(), "1000000".into())],
+        );
+        assert_eq!(transport.get(url), Err(ClientError::ResponseTooLarge));
+    })
+}
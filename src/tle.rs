@@ -0,0 +1,426 @@
+//! The `age` based format of drand's `tle` CLI and `tlock-js`, meant for timelocked files created
+//! by external tooling to be decrypted here (and the other way around). The tests only cover
+//! files created here: none created by `tle` or `tlock-js` is checked yet.
+//!
+//! A file is an `age` v1 file with a single `tlock` recipient stanza:
+//! ```text
+//! age-encryption.org/v1
+//! -> tlock {round} {chain hash, hex}
+//! {base64 of the tlock ciphertext of the file key: U || V || W}
+//! --- {base64 of the header MAC}
+//! {16 bytes nonce}{payload}
+//! ```
+//! The 16 bytes file key is timelock encrypted to the round. The header is authenticated with
+//! HMAC-SHA256, and the payload encrypted with ChaCha20-Poly1305 in 64 KiB chunks (age STREAM),
+//! with keys derived from the file key by HKDF-SHA256.
+//! Files can also be ASCII armored, as PEM with the `AGE ENCRYPTED FILE` label.
+
+extern crate alloc;
+
+use alloc::{format, string::String};
+
+use base64::{
+    engine::general_purpose::{STANDARD, STANDARD_NO_PAD},
+    Engine,
+};
+use chacha20poly1305::{aead::Aead, ChaCha20Poly1305, KeyInit};
+use codec::{Decode, Encode, MaxEncodedLen};
+use hkdf::Hkdf;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use sp_runtime::{traits::ConstU32, BoundedVec, RuntimeDebug};
+use sp_std::{str, vec::Vec};
+
+use crate::{
+    tlock::{self, Ciphertext, TlockError},
+    util::{hex_to_vec_u8, vec_u8_to_hex},
+    Info, Round,
+};
+
+/// First line of the armored format.
+pub const ARMOR_BEGIN: &str = "-----BEGIN AGE ENCRYPTED FILE-----";
+/// Last line of the armored format.
+pub const ARMOR_END: &str = "-----END AGE ENCRYPTED FILE-----";
+
+const INTRO: &[u8] = b"age-encryption.org/v1\n";
+const STANZA_PREFIX: &[u8] = b"-> ";
+const MAC_PREFIX: &[u8] = b"---";
+const STANZA_TYPE: &str = "tlock";
+const COLUMNS: usize = 64;
+const FILE_KEY_LEN: usize = 16;
+const NONCE_LEN: usize = 16;
+const CHUNK_SIZE: usize = 64 * 1024;
+const TAG_LEN: usize = 16;
+
+/// `tle` format errors
+#[derive(PartialEq, Eq, RuntimeDebug)]
+pub enum TleError {
+    /// Not an `age` v1 file (or armor), or a malformed header
+    InvalidFormat,
+    /// The file has no `tlock` stanza, or other stanzas that can't be handled here
+    UnsupportedRecipient,
+    /// The file was encrypted to another chain
+    WrongChain,
+    /// The file was encrypted to another round
+    WrongRound,
+    /// Timelock decryption of the file key failed
+    Tlock(TlockError),
+    /// The header was tampered with
+    InvalidMac,
+    /// The payload is truncated or was tampered with
+    InvalidPayload,
+}
+
+impl From<TlockError> for TleError {
+    fn from(e: TlockError) -> Self {
+        TleError::Tlock(e)
+    }
+}
+
+/// The `tlock` stanza of a file: what the file is encrypted to, and the encrypted file key.
+/// It is enough for a pallet to know when a submitted file can be decrypted.
+#[derive(
+    Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, scale_info::TypeInfo, MaxEncodedLen,
+)]
+pub struct TleHeader {
+    pub round: u64,
+    pub chain_hash: BoundedVec<u8, ConstU32<32>>,
+    /// The timelock encrypted file key.
+    pub file_key: Ciphertext,
+}
+
+/// Encrypts `plaintext` to round `round` of the chain `info`, in the binary `tle` format.
+/// `entropy` must be fresh uniformly random bytes: it provides the file key, the timelock
+/// encryption randomness and the payload nonce.
+pub fn encrypt(
+    info: &Info,
+    round: u64,
+    plaintext: &[u8],
+    entropy: &[u8; 64],
+) -> Result<Vec<u8>, TleError> {
+    let file_key = &entropy[..FILE_KEY_LEN];
+    let mut sigma = [0u8; 32];
+    sigma.copy_from_slice(&entropy[FILE_KEY_LEN..FILE_KEY_LEN + 32]);
+    let nonce = &entropy[FILE_KEY_LEN + 32..];
+
+    let header = TleHeader {
+        round,
+        chain_hash: info.hash.clone(),
+        file_key: tlock::encrypt(info, round, file_key, &sigma)?,
+    };
+
+    let mut file = encode_header(&header, file_key);
+    file.extend(nonce);
+    file.extend(encrypt_payload(file_key, nonce, plaintext));
+    Ok(file)
+}
+
+/// Decrypts a `tle` file (binary or armored) encrypted to `round` of the chain `info`.
+pub fn decrypt(info: &Info, round: &Round, file: &[u8]) -> Result<Vec<u8>, TleError> {
+    let dearmored;
+    let file = if file.starts_with(ARMOR_BEGIN.as_bytes()) {
+        let armored = str::from_utf8(file).map_err(|_| TleError::InvalidFormat)?;
+        dearmored = dearmor(armored)?;
+        &dearmored[..]
+    } else {
+        file
+    };
+
+    let parsed = parse(file)?;
+    if parsed.header.chain_hash != info.hash {
+        return Err(TleError::WrongChain);
+    }
+    if parsed.header.round != round.round {
+        return Err(TleError::WrongRound);
+    }
+
+    let file_key = tlock::decrypt(info, round, &parsed.header.file_key)?;
+    if file_key.len() != FILE_KEY_LEN {
+        return Err(TleError::Tlock(TlockError::InvalidCiphertext));
+    }
+    if header_mac(&file_key, parsed.mac_input) != parsed.mac {
+        return Err(TleError::InvalidMac);
+    }
+
+    decrypt_payload(&file_key, parsed.nonce, parsed.payload)
+}
+
+/// Reads the `tlock` stanza of a `tle` file (binary or armored), without decrypting anything.
+pub fn parse_header(file: &[u8]) -> Result<TleHeader, TleError> {
+    if file.starts_with(ARMOR_BEGIN.as_bytes()) {
+        let armored = str::from_utf8(file).map_err(|_| TleError::InvalidFormat)?;
+        return parse(&dearmor(armored)?).map(|parsed| parsed.header);
+    }
+    parse(file).map(|parsed| parsed.header)
+}
+
+/// ASCII armors a binary file: PEM style, with lines of 64 columns of padded base64.
+pub fn armor(file: &[u8]) -> String {
+    let encoded = STANDARD.encode(file);
+    let mut armored = String::from(ARMOR_BEGIN);
+    armored.push('\n');
+    for line in encoded.as_bytes().chunks(COLUMNS) {
+        // base64 is ASCII
+        armored.push_str(str::from_utf8(line).unwrap_or_default());
+        armored.push('\n');
+    }
+    armored.push_str(ARMOR_END);
+    armored.push('\n');
+    armored
+}
+
+/// Decodes an ASCII armored file. Surrounding whitespace is ignored.
+pub fn dearmor(armored: &str) -> Result<Vec<u8>, TleError> {
+    let mut lines = armored
+        .trim()
+        .lines()
+        .map(|line| line.trim_end_matches('\r'));
+    if lines.next() != Some(ARMOR_BEGIN) {
+        return Err(TleError::InvalidFormat);
+    }
+
+    let mut encoded = String::new();
+    let mut ended = false;
+    for line in lines {
+        if ended {
+            return Err(TleError::InvalidFormat);
+        }
+        if line == ARMOR_END {
+            ended = true;
+        } else if line.len() > COLUMNS {
+            return Err(TleError::InvalidFormat);
+        } else {
+            encoded.push_str(line);
+        }
+    }
+    if !ended {
+        return Err(TleError::InvalidFormat);
+    }
+
+    STANDARD
+        .decode(encoded)
+        .map_err(|_| TleError::InvalidFormat)
+}
+
+/// A binary file split in its parts.
+struct Parsed<'a> {
+    header: TleHeader,
+    /// The header bytes authenticated by the MAC, up to and including `---`.
+    mac_input: &'a [u8],
+    mac: [u8; 32],
+    nonce: &'a [u8],
+    payload: &'a [u8],
+}
+
+fn parse(file: &[u8]) -> Result<Parsed<'_>, TleError> {
+    let mut rest = file.strip_prefix(INTRO).ok_or(TleError::InvalidFormat)?;
+    let mut header = None;
+
+    loop {
+        let line_start = file.len() - rest.len();
+        let line = next_line(&mut rest)?;
+
+        if let Some(args) = line.strip_prefix(STANZA_PREFIX) {
+            // a stanza body is base64 wrapped at 64 columns, ending with a shorter line
+            let mut body = Vec::new();
+            loop {
+                let body_line = next_line(&mut rest)?;
+                if body_line.len() > COLUMNS {
+                    return Err(TleError::InvalidFormat);
+                }
+                body.extend(body_line);
+                if body_line.len() < COLUMNS {
+                    break;
+                }
+            }
+            if header.is_some() {
+                // tle files have a single recipient
+                return Err(TleError::UnsupportedRecipient);
+            }
+            header = Some(parse_stanza(args, &body)?);
+        } else if let Some(mac) = line.strip_prefix(MAC_PREFIX) {
+            let mac = mac.strip_prefix(b" ").ok_or(TleError::InvalidFormat)?;
+            let mac: [u8; 32] = STANDARD_NO_PAD
+                .decode(mac)
+                .ok()
+                .and_then(|mac| mac.try_into().ok())
+                .ok_or(TleError::InvalidFormat)?;
+            if rest.len() < NONCE_LEN {
+                return Err(TleError::InvalidPayload);
+            }
+
+            return Ok(Parsed {
+                header: header.ok_or(TleError::UnsupportedRecipient)?,
+                mac_input: &file[..line_start + MAC_PREFIX.len()],
+                mac,
+                nonce: &rest[..NONCE_LEN],
+                payload: &rest[NONCE_LEN..],
+            });
+        } else {
+            return Err(TleError::InvalidFormat);
+        }
+    }
+}
+
+/// Parses `tlock {round} {chain hash}` and the stanza body `U || V || W`.
+fn parse_stanza(args: &[u8], body: &[u8]) -> Result<TleHeader, TleError> {
+    let args = str::from_utf8(args).map_err(|_| TleError::InvalidFormat)?;
+    let mut args = args.split(' ');
+    if args.next() != Some(STANZA_TYPE) {
+        return Err(TleError::UnsupportedRecipient);
+    }
+    let round = args
+        .next()
+        .and_then(|round| round.parse().ok())
+        .ok_or(TleError::InvalidFormat)?;
+    let chain_hash = args
+        .next()
+        .and_then(hex_to_vec_u8)
+        .and_then(|hash| hash.try_into().ok())
+        .ok_or(TleError::InvalidFormat)?;
+    if args.next().is_some() {
+        return Err(TleError::InvalidFormat);
+    }
+
+    let body = STANDARD_NO_PAD
+        .decode(body)
+        .map_err(|_| TleError::InvalidFormat)?;
+    // U is a compressed G1 (48 bytes) or G2 (96 bytes) point, then V and W have the file key size
+    let u_len = body
+        .len()
+        .checked_sub(2 * FILE_KEY_LEN)
+        .filter(|len| *len == 48 || *len == 96)
+        .ok_or(TleError::InvalidFormat)?;
+    let file_key = Ciphertext {
+        u: body[..u_len]
+            .to_vec()
+            .try_into()
+            .map_err(|_| TleError::InvalidFormat)?,
+        v: body[u_len..u_len + FILE_KEY_LEN]
+            .to_vec()
+            .try_into()
+            .map_err(|_| TleError::InvalidFormat)?,
+        w: body[u_len + FILE_KEY_LEN..]
+            .to_vec()
+            .try_into()
+            .map_err(|_| TleError::InvalidFormat)?,
+    };
+
+    Ok(TleHeader {
+        round,
+        chain_hash,
+        file_key,
+    })
+}
+
+fn encode_header(header: &TleHeader, file_key: &[u8]) -> Vec<u8> {
+    let mut encoded = INTRO.to_vec();
+    encoded.extend(
+        format!(
+            "-> {} {} {}\n",
+            STANZA_TYPE,
+            header.round,
+            vec_u8_to_hex(&header.chain_hash)
+        )
+        .as_bytes(),
+    );
+
+    let mut body = header.file_key.u.to_vec();
+    body.extend(header.file_key.v.iter());
+    body.extend(header.file_key.w.iter());
+    let body = STANDARD_NO_PAD.encode(body);
+    for line in body.as_bytes().chunks(COLUMNS) {
+        encoded.extend(line);
+        encoded.push(b'\n');
+    }
+    // the body always ends with a line shorter than 64 columns, possibly empty
+    let last_line_len = body.len() % COLUMNS;
+    if last_line_len == 0 {
+        encoded.push(b'\n');
+    }
+
+    encoded.extend(MAC_PREFIX);
+    let mac = header_mac(file_key, &encoded);
+    encoded.push(b' ');
+    encoded.extend(STANDARD_NO_PAD.encode(mac).as_bytes());
+    encoded.push(b'\n');
+    encoded
+}
+
+fn next_line<'a>(rest: &mut &'a [u8]) -> Result<&'a [u8], TleError> {
+    let end = rest
+        .iter()
+        .position(|b| *b == b'\n')
+        .ok_or(TleError::InvalidFormat)?;
+    let line = &rest[..end];
+    *rest = &rest[end + 1..];
+    Ok(line)
+}
+
+fn hkdf(file_key: &[u8], salt: &[u8], info: &[u8]) -> [u8; 32] {
+    let mut key = [0u8; 32];
+    Hkdf::<Sha256>::new(Some(salt), file_key)
+        .expand(info, &mut key)
+        .expect("32 bytes is a valid output length for HKDF-SHA256");
+    key
+}
+
+fn header_mac(file_key: &[u8], header: &[u8]) -> [u8; 32] {
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(&hkdf(file_key, &[], b"header"))
+        .expect("HMAC accepts keys of any size");
+    mac.update(header);
+    mac.finalize().into_bytes().into()
+}
+
+/// Nonce of the STREAM chunk `counter`: an 11 bytes big-endian counter and a last chunk flag.
+fn chunk_nonce(counter: u64, last: bool) -> [u8; 12] {
+    let mut nonce = [0u8; 12];
+    nonce[3..11].copy_from_slice(&counter.to_be_bytes());
+    nonce[11] = last as u8;
+    nonce
+}
+
+fn payload_cipher(file_key: &[u8], nonce: &[u8]) -> ChaCha20Poly1305 {
+    ChaCha20Poly1305::new(&hkdf(file_key, nonce, b"payload").into())
+}
+
+fn encrypt_payload(file_key: &[u8], nonce: &[u8], plaintext: &[u8]) -> Vec<u8> {
+    let cipher = payload_cipher(file_key, nonce);
+    let chunks: Vec<&[u8]> = if plaintext.is_empty() {
+        Vec::from([plaintext])
+    } else {
+        plaintext.chunks(CHUNK_SIZE).collect()
+    };
+
+    let mut payload = Vec::with_capacity(plaintext.len() + chunks.len() * TAG_LEN);
+    for (counter, chunk) in chunks.iter().enumerate() {
+        let last = counter + 1 == chunks.len();
+        let encrypted = cipher
+            .encrypt(&chunk_nonce(counter as u64, last).into(), *chunk)
+            .expect("chunks are far below the ChaCha20-Poly1305 size limit");
+        payload.extend(encrypted);
+    }
+    payload
+}
+
+fn decrypt_payload(file_key: &[u8], nonce: &[u8], payload: &[u8]) -> Result<Vec<u8>, TleError> {
+    let cipher = payload_cipher(file_key, nonce);
+    if payload.len() < TAG_LEN {
+        return Err(TleError::InvalidPayload);
+    }
+
+    let chunks: Vec<&[u8]> = payload.chunks(CHUNK_SIZE + TAG_LEN).collect();
+    let mut plaintext = Vec::with_capacity(payload.len());
+    for (counter, chunk) in chunks.iter().enumerate() {
+        let last = counter + 1 == chunks.len();
+        let decrypted = cipher
+            .decrypt(&chunk_nonce(counter as u64, last).into(), *chunk)
+            .map_err(|_| TleError::InvalidPayload)?;
+        // only an empty file has an empty (last) chunk
+        if decrypted.is_empty() && counter > 0 {
+            return Err(TleError::InvalidPayload);
+        }
+        plaintext.extend(decrypted);
+    }
+    Ok(plaintext)
+}
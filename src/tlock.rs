@@ -0,0 +1,238 @@
+//! Timelock encryption (tlock): encrypt to a future round of a drand chain, so that anyone can
+//! decrypt once the chain publishes the signature of that round.
+//!
+//! This is the Boneh-Franklin identity based encryption (with the Fujisaki-Okamoto transform)
+//! used by drand's `tle` and `tlock-js`, where the identity of round `n` is `sha256(n)` and the
+//! round signature is the identity's private key. It requires an unchained scheme, so that the
+//! signature of a round doesn't depend on the previous one.
+//!
+//! Given the chain public key `pk`, the identity point `Q = H(sha256(n))` on the signature group
+//! and the generator `P` of the public key group, encrypting `msg` with random `sigma` is:
+//! - `r = H3(sigma, msg)`
+//! - `U = r·P`
+//! - `V = sigma ⊕ H2(e(pk, Q)^r)`
+//! - `W = msg ⊕ H4(sigma)`
+//!
+//! Decrypting with the round signature `s` recovers `sigma = V ⊕ H2(e(U, s))`, then
+//! `msg = W ⊕ H4(sigma)`, and checks that `U == H3(sigma, msg)·P`.
+
+use ark_bls12_381::{Bls12_381, Fr, G1Affine, G2Affine};
+use ark_ec::{
+    pairing::{Pairing, PairingOutput},
+    AffineRepr, CurveGroup,
+};
+use ark_ff::{BigInt, PrimeField};
+use ark_serialize::CanonicalSerialize;
+use codec::{Decode, Encode, MaxEncodedLen};
+use sha2::{Digest, Sha256};
+use sp_runtime::{traits::ConstU32, BoundedVec, RuntimeDebug};
+use sp_std::vec::Vec;
+
+use crate::{
+    bls::{self, SignaturePoint},
+    Client, Info, Round,
+};
+
+/// Maximum length of an encrypted message. Longer payloads should be encrypted with a symmetric
+/// key, and the key timelocked (this is what the `tle` format does).
+pub const MAX_MESSAGE_LEN: usize = 32;
+
+const H2_TAG: &[u8] = b"IBE-H2";
+const H3_TAG: &[u8] = b"IBE-H3";
+const H4_TAG: &[u8] = b"IBE-H4";
+
+/// Timelock encryption errors
+#[derive(PartialEq, Eq, RuntimeDebug)]
+pub enum TlockError {
+    /// Chained schemes can't be used for timelock encryption
+    ChainedScheme,
+    /// The message is longer than `MAX_MESSAGE_LEN`, or empty
+    InvalidMessageLength,
+    /// The chain public key is not a valid point
+    InvalidPublicKey,
+    /// The round signature doesn't verify against the chain public key
+    InvalidSignature,
+    /// `U` is not a valid point, or `V` and `W` have different lengths
+    InvalidCiphertext,
+    /// The ciphertext was not encrypted to this round, or was tampered with
+    DecryptionFailed,
+}
+
+/// A message encrypted to a round. It doesn't record the round or the chain it was encrypted to,
+/// which are up to the caller (or the `tle` envelope) to keep track of.
+#[derive(
+    Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, scale_info::TypeInfo, MaxEncodedLen,
+)]
+pub struct Ciphertext {
+    /// `r·P`, a compressed point on the public key group (48 bytes on G1, 96 bytes on G2).
+    pub u: BoundedVec<u8, ConstU32<96>>,
+    /// `sigma ⊕ H2(e(pk, Q)^r)`
+    pub v: BoundedVec<u8, ConstU32<32>>,
+    /// `msg ⊕ H4(sigma)`
+    pub w: BoundedVec<u8, ConstU32<32>>,
+}
+
+/// The identity of a round: `sha256` of its number, which is also the message signed by
+/// unchained schemes.
+pub fn identity(round: u64) -> [u8; 32] {
+    Sha256::digest(round.to_be_bytes()).into()
+}
+
+/// Encrypts `msg` to round `round` of the chain `info`.
+/// `sigma` must be fresh uniformly random bytes, never reused: anyone knowing it can decrypt.
+pub fn encrypt(
+    info: &Info,
+    round: u64,
+    msg: &[u8],
+    sigma: &[u8; 32],
+) -> Result<Ciphertext, TlockError> {
+    if info.scheme.is_chained() {
+        return Err(TlockError::ChainedScheme);
+    }
+    if msg.is_empty() || msg.len() > MAX_MESSAGE_LEN {
+        return Err(TlockError::InvalidMessageLength);
+    }
+    let sigma = &sigma[..msg.len()];
+
+    let q_id = bls::hash_to_signature_group(info.scheme, &identity(round))
+        .ok_or(TlockError::InvalidPublicKey)?;
+    let r = h3(sigma, msg);
+
+    let (u, gid) = match q_id {
+        SignaturePoint::G2(q_id) => {
+            let pk = bls::g1_from_bytes(&info.public_key).ok_or(TlockError::InvalidPublicKey)?;
+            let u = (G1Affine::generator() * r).into_affine();
+            (compress(&u), Bls12_381::pairing(pk, q_id))
+        }
+        SignaturePoint::G1(q_id) => {
+            let pk = bls::g2_from_bytes(&info.public_key).ok_or(TlockError::InvalidPublicKey)?;
+            let u = (G2Affine::generator() * r).into_affine();
+            (compress(&u), Bls12_381::pairing(q_id, pk))
+        }
+    };
+
+    let v = xor(sigma, &h2(&(gid * r), msg.len()));
+    let w = xor(msg, &h4(sigma, msg.len()));
+
+    Ok(Ciphertext {
+        u: bounded(u)?,
+        v: bounded(v)?,
+        w: bounded(w)?,
+    })
+}
+
+/// Decrypts a ciphertext encrypted to `round` with the chain `info`. The round signature is
+/// verified first, so a bogus round is reported as `InvalidSignature`.
+pub fn decrypt(info: &Info, round: &Round, ciphertext: &Ciphertext) -> Result<Vec<u8>, TlockError> {
+    if info.scheme.is_chained() {
+        return Err(TlockError::ChainedScheme);
+    }
+    Client::verify_round(round, info).map_err(|_| TlockError::InvalidSignature)?;
+
+    let Ciphertext { u, v, w } = ciphertext;
+    if v.len() != w.len() || v.is_empty() {
+        return Err(TlockError::InvalidCiphertext);
+    }
+
+    let r_gid = if info.scheme.public_key_on_g2() {
+        let u = bls::g2_from_bytes(u).ok_or(TlockError::InvalidCiphertext)?;
+        let signature = bls::g1_from_bytes(&round.signature).ok_or(TlockError::InvalidSignature)?;
+        Bls12_381::pairing(signature, u)
+    } else {
+        let u = bls::g1_from_bytes(u).ok_or(TlockError::InvalidCiphertext)?;
+        let signature = bls::g2_from_bytes(&round.signature).ok_or(TlockError::InvalidSignature)?;
+        Bls12_381::pairing(u, signature)
+    };
+
+    let sigma = xor(v, &h2(&r_gid, v.len()));
+    let msg = xor(w, &h4(&sigma, w.len()));
+
+    // Fujisaki-Okamoto check: U must be the commitment to sigma and msg
+    let r = h3(&sigma, &msg);
+    let expected_u = if info.scheme.public_key_on_g2() {
+        compress(&(G2Affine::generator() * r).into_affine())
+    } else {
+        compress(&(G1Affine::generator() * r).into_affine())
+    };
+    if expected_u != u.as_slice() {
+        return Err(TlockError::DecryptionFailed);
+    }
+
+    Ok(msg)
+}
+
+/// `H2(gt) = sha256("IBE-H2" || gt)`, truncated to `len` bytes.
+fn h2(gt: &PairingOutput<Bls12_381>, len: usize) -> Vec<u8> {
+    let mut hasher = Sha256::new();
+    hasher.update(H2_TAG);
+    hasher.update(gt_to_bytes(gt));
+    hasher.finalize()[..len].to_vec()
+}
+
+/// A target group element serialized as drand's kyber does: big-endian, with the highest
+/// coefficients first. That's arkworks' serialization (little-endian, lowest coefficients
+/// first) reversed.
+pub(crate) fn gt_to_bytes(gt: &PairingOutput<Bls12_381>) -> Vec<u8> {
+    let mut gt_bytes = Vec::new();
+    gt.serialize_compressed(&mut gt_bytes)
+        .expect("serializing to a Vec can't fail");
+    gt_bytes.reverse();
+    gt_bytes
+}
+
+/// `H3(sigma, msg)`: a scalar derived from `h = sha256("IBE-H3" || sigma || msg)` by rejection
+/// sampling on `sha256(i || h)` (with `i` a little-endian counter starting at 1 and the top bit
+/// cleared), read as a big-endian integer.
+fn h3(sigma: &[u8], msg: &[u8]) -> Fr {
+    let mut hasher = Sha256::new();
+    hasher.update(H3_TAG);
+    hasher.update(sigma);
+    hasher.update(msg);
+    let buffer = hasher.finalize();
+
+    (1..=u16::MAX)
+        .find_map(|i| {
+            let mut hasher = Sha256::new();
+            hasher.update(i.to_le_bytes());
+            hasher.update(buffer);
+            let mut hashed: [u8; 32] = hasher.finalize().into();
+            hashed[0] >>= 1;
+            scalar_from_be_bytes(&hashed)
+        })
+        .expect("a candidate below the group order is found after a few iterations")
+}
+
+/// `H4(sigma) = sha256("IBE-H4" || sigma)`, truncated to `len` bytes.
+fn h4(sigma: &[u8], len: usize) -> Vec<u8> {
+    let mut hasher = Sha256::new();
+    hasher.update(H4_TAG);
+    hasher.update(sigma);
+    hasher.finalize()[..len].to_vec()
+}
+
+/// Reads a big-endian scalar, rejecting values not below the group order.
+fn scalar_from_be_bytes(bytes: &[u8; 32]) -> Option<Fr> {
+    let mut limbs = [0u64; 4];
+    for (i, chunk) in bytes.rchunks(8).enumerate() {
+        let mut limb = [0u8; 8];
+        limb.copy_from_slice(chunk);
+        limbs[i] = u64::from_be_bytes(limb);
+    }
+    Fr::from_bigint(BigInt::new(limbs))
+}
+
+fn compress<P: CanonicalSerialize>(point: &P) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    point
+        .serialize_compressed(&mut bytes)
+        .expect("serializing to a Vec can't fail");
+    bytes
+}
+
+fn xor(a: &[u8], b: &[u8]) -> Vec<u8> {
+    a.iter().zip(b).map(|(a, b)| a ^ b).collect()
+}
+
+fn bounded<const S: u32>(bytes: Vec<u8>) -> Result<BoundedVec<u8, ConstU32<S>>, TlockError> {
+    bytes.try_into().map_err(|_| TlockError::InvalidCiphertext)
+}
@@ -0,0 +1,304 @@
+//! Transports used by the `Client` to fetch raw bytes from a drand endpoint.
+//! The `Client` only deals with building URLs, parsing and verification, while a `Transport`
+//! decides how the bytes are actually fetched:
+//! 1. `OffchainTransport` (the default) uses the offchain worker HTTP API of `sp_runtime`.
+//! 2. `MockTransport` serves canned responses from memory, which is handy for tests and tools
+//!    that don't run inside `TestExternalities`.
+
+extern crate alloc;
+
+use alloc::{collections::BTreeMap, string::String};
+use core::cell::RefCell;
+
+use codec::{Decode, Encode, MaxEncodedLen};
+use serde::{Deserialize, Serialize};
+use sp_runtime::{
+    offchain::{http::Request, Duration},
+    RuntimeDebug,
+};
+use sp_std::vec::Vec;
+
+use crate::ClientError;
+
+/// Status code, headers and body of an HTTP response.
+#[derive(Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct HttpResponse {
+    pub code: u16,
+    pub body: Vec<u8>,
+    /// Header names and values, in the order they were received.
+    pub headers: Vec<(String, String)>,
+}
+
+impl HttpResponse {
+    /// A `200 OK` response with the given body.
+    pub fn ok(body: Vec<u8>) -> Self {
+        HttpResponse {
+            code: 200,
+            body,
+            headers: Vec::new(),
+        }
+    }
+
+    /// Adds a header to the response.
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Value of the first header named `name`, which is case insensitive.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Fetches bytes over HTTP on behalf of the `Client`.
+pub trait Transport {
+    /// HTTP GET on `url`. Returns the response even if the status code is not `200`,
+    /// errors are only for requests that could not be completed.
+    fn get(&self, url: &str) -> Result<HttpResponse, ClientError>;
+
+    /// HTTP GET on `url`, sending `headers` along. Transports that can't send headers ignore
+    /// them.
+    fn get_with_headers(
+        &self,
+        url: &str,
+        headers: &[(String, String)],
+    ) -> Result<HttpResponse, ClientError> {
+        if !headers.is_empty() {
+            log::warn!("Headers not sent to {}", url);
+        }
+        self.get(url)
+    }
+}
+
+impl<T: Transport + ?Sized> Transport for &T {
+    fn get(&self, url: &str) -> Result<HttpResponse, ClientError> {
+        (**self).get(url)
+    }
+
+    fn get_with_headers(
+        &self,
+        url: &str,
+        headers: &[(String, String)],
+    ) -> Result<HttpResponse, ClientError> {
+        (**self).get_with_headers(url, headers)
+    }
+}
+
+/// Default maximum size of a response body. drand responses are well under a kilobyte, the
+/// largest being `/chains` of a relay serving many chains.
+pub const DEFAULT_MAX_BODY_SIZE: u32 = 64 * 1024;
+
+/// Transport backed by the offchain worker HTTP API. Only usable from an offchain worker
+/// (or in tests, with an `OffchainWorkerExt` registered).
+/// The response body is read in chunks, up to `max_body_size` bytes and within its own
+/// deadline, so an endpoint can't make the worker allocate unbounded memory or hang on a slow
+/// body. Part of the `Client` configuration, used by the methods that don't take a transport.
+#[derive(
+    Encode,
+    Decode,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    RuntimeDebug,
+    scale_info::TypeInfo,
+    MaxEncodedLen,
+    Serialize,
+    Deserialize,
+)]
+pub struct OffchainTransport {
+    /// Time allowed to send the request and receive the response head, in milliseconds.
+    pub timeout_ms: u64,
+    /// Time allowed to read the response body, in milliseconds.
+    pub read_timeout_ms: u64,
+    /// Larger bodies fail with `ClientError::ResponseTooLarge`.
+    pub max_body_size: u32,
+}
+
+impl Default for OffchainTransport {
+    fn default() -> Self {
+        OffchainTransport {
+            timeout_ms: 2_000,
+            read_timeout_ms: 2_000,
+            max_body_size: DEFAULT_MAX_BODY_SIZE,
+        }
+    }
+}
+
+impl OffchainTransport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the maximum size of a response body.
+    pub fn with_max_body_size(mut self, max_body_size: u32) -> Self {
+        self.max_body_size = max_body_size;
+        self
+    }
+
+    /// Sets the time allowed to send the request and receive the response head.
+    pub fn with_timeout(mut self, timeout_ms: u64) -> Self {
+        self.timeout_ms = timeout_ms;
+        self
+    }
+
+    /// Sets the time allowed to read the response body.
+    pub fn with_read_timeout(mut self, read_timeout_ms: u64) -> Self {
+        self.read_timeout_ms = read_timeout_ms;
+        self
+    }
+}
+
+impl Transport for OffchainTransport {
+    fn get(&self, url: &str) -> Result<HttpResponse, ClientError> {
+        self.get_with_headers(url, &[])
+    }
+
+    fn get_with_headers(
+        &self,
+        url: &str,
+        headers: &[(String, String)],
+    ) -> Result<HttpResponse, ClientError> {
+        // We want to keep the offchain worker execution time reasonable, so we set a deadline
+        // (2s by default) to complete the external call.
+        // You can also wait idefinitely for the response, however you may still get a timeout
+        // coming from the host machine.
+        let send_deadline =
+            sp_io::offchain::timestamp().add(Duration::from_millis(self.timeout_ms));
+        // Initiate an external HTTP GET request.
+        // This is using high-level wrappers from `sp_runtime`, for the low-level calls that
+        // you can find in `sp_io`. The API is trying to be similar to `reqwest`, but
+        // since we are running in a custom WASM execution environment we can't simply
+        // import the library here.
+        let request = headers
+            .iter()
+            .fold(Request::get(url), |request, (name, value)| {
+                request.add_header(name, value)
+            });
+        // We set the deadline for sending of the request, note that awaiting response can
+        // have a separate deadline. Next we send the request, before that it's also possible
+        // to alter request headers or stream body content in case of non-GET requests.
+        let pending = request
+            .deadline(send_deadline)
+            .send()
+            .map_err(|_| ClientError::Http)?;
+
+        // The request is already being processed by the host, we are free to do anything
+        // else in the worker (we can send multiple concurrent requests too).
+        // At some point however we probably want to check the response though,
+        // so we can block current thread and wait for it to finish.
+        // Note that since the request is being driven by the host, we don't have to wait
+        // for the request to have it complete, we will just not read the response.
+        let mut response = pending
+            .try_wait(send_deadline)
+            .map_err(|_| ClientError::Http)?
+            .map_err(|_| ClientError::Http)?;
+
+        let headers: Vec<(String, String)> = response
+            .headers()
+            .raw
+            .iter()
+            .filter_map(|(name, value)| {
+                Some((
+                    String::from_utf8(name.clone()).ok()?,
+                    String::from_utf8(value.clone()).ok()?,
+                ))
+            })
+            .collect();
+
+        let max_body_size = self.max_body_size as usize;
+        let declared_size = headers
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+            .and_then(|(_, length)| length.parse::<usize>().ok());
+        if matches!(declared_size, Some(size) if size > max_body_size) {
+            log::warn!("Response body of {:?} bytes is too large", declared_size);
+            return Err(ClientError::ResponseTooLarge);
+        }
+
+        // Next we read the response body, which the host hands over in chunks, until the body
+        // ends, it gets too large, or the read deadline passes.
+        let code = response.code;
+        let mut chunks = response.body();
+        chunks.deadline(
+            sp_io::offchain::timestamp().add(Duration::from_millis(self.read_timeout_ms)),
+        );
+        let mut body = Vec::new();
+        for byte in chunks.by_ref() {
+            if body.len() == max_body_size {
+                log::warn!("Response body is larger than {} bytes", max_body_size);
+                return Err(ClientError::ResponseTooLarge);
+            }
+            body.push(byte);
+        }
+        if let Some(e) = chunks.error() {
+            log::warn!("Failed to read response body: {:?}", e);
+            return Err(ClientError::Http);
+        }
+
+        Ok(HttpResponse {
+            code,
+            body,
+            headers,
+        })
+    }
+}
+
+/// In-memory transport serving canned responses keyed by URL.
+/// Every requested URL is recorded, so callers can assert on what was (not) fetched.
+#[derive(Default, Clone, RuntimeDebug)]
+pub struct MockTransport {
+    responses: BTreeMap<String, HttpResponse>,
+    requests: RefCell<Vec<String>>,
+    headers: RefCell<Vec<Vec<(String, String)>>>,
+}
+
+impl MockTransport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Serves `body` with a `200 OK` for `url`.
+    pub fn with_response(mut self, url: &str, body: &[u8]) -> Self {
+        self.insert(url, HttpResponse::ok(body.to_vec()));
+        self
+    }
+
+    /// Serves `response` for `url`, replacing any previous response.
+    pub fn insert(&mut self, url: &str, response: HttpResponse) {
+        self.responses.insert(url.into(), response);
+    }
+
+    /// URLs requested so far, in order.
+    pub fn requests(&self) -> Vec<String> {
+        self.requests.borrow().clone()
+    }
+
+    /// Headers sent with each request so far, in order.
+    pub fn sent_headers(&self) -> Vec<Vec<(String, String)>> {
+        self.headers.borrow().clone()
+    }
+}
+
+impl Transport for MockTransport {
+    fn get(&self, url: &str) -> Result<HttpResponse, ClientError> {
+        self.get_with_headers(url, &[])
+    }
+
+    fn get_with_headers(
+        &self,
+        url: &str,
+        headers: &[(String, String)],
+    ) -> Result<HttpResponse, ClientError> {
+        self.requests.borrow_mut().push(url.into());
+        self.headers.borrow_mut().push(headers.to_vec());
+        self.responses.get(url).cloned().ok_or_else(|| {
+            log::warn!("No mocked response for {}", url);
+            ClientError::Http
+        })
+    }
+}
@@ -1,6 +1,6 @@
 extern crate alloc;
 
-use alloc::vec::Vec;
+use alloc::{string::String, vec::Vec};
 use sp_runtime::{traits::ConstU32, BoundedVec};
 
 // return Option since Error is part of std
@@ -18,14 +18,28 @@ pub fn hex_to_vec_u8(s: &str) -> Option<Vec<u8>> {
     }
 }
 
+pub fn vec_u8_to_hex(bytes: &[u8]) -> String {
+    const HEX: &[u8; 16] = b"0123456789abcdef";
+    bytes
+        .iter()
+        .flat_map(|b| {
+            [
+                HEX[(b >> 4) as usize] as char,
+                HEX[(b & 0xf) as usize] as char,
+            ]
+        })
+        .collect()
+}
+
 // TODO `impl TryFrom<Value> for BoundedVec...` instead
 pub fn hex_json_value_to_bounded_vec_u8<const S: u32>(
     val: &serde_json::value::Value,
 ) -> BoundedVec<u8, ConstU32<S>> {
-    // TODO remove unwraps (runtime should not panic)
-    let bytes = hex_to_vec_u8(val.as_str().unwrap()).unwrap();
-
-    bytes.try_into().unwrap()
+    // a missing (null), invalid or oversized value is decoded as no bytes, which then fails
+    // verification, rather than panicking on what a relay served
+    hex_to_vec_u8(val.as_str().unwrap_or_default())
+        .and_then(|bytes| bytes.try_into().ok())
+        .unwrap_or_default()
 }
 
 #[test]
@@ -35,6 +49,12 @@ fn test_hex_to_vec_u8_uneven() {
     assert!(bytes.is_none());
 }
 
+#[test]
+fn test_vec_u8_to_hex() {
+    let hex = "ccbdad137f3bc5e01ebd8c7529abc31813a0566b84e6fd765a661398e9bcbc2f";
+    assert_eq!(vec_u8_to_hex(&hex_to_vec_u8(hex).unwrap()), hex);
+}
+
 #[test]
 fn test_hex_to_vec_u8() {
     let hex = "ccbdad137f3bc5e01ebd8c7529abc31813a0566b84e6fd765a661398e9bcbc2f";